@@ -0,0 +1,30 @@
+use cosmwasm_std::{Api, CanonicalAddr, Env, HumanAddr, StdError, StdResult};
+
+use crate::error::ContractError;
+
+/*
+  Every address that arrives in a message goes through here before it is stored or compared. The chain's
+  canonical_address rejects anything that isn't a real address, so a typo or stray whitespace is refused up
+  front instead of becoming a grant nobody can ever use.
+*/
+pub fn canonicalize<A: Api>(api: &A, address: &str) -> StdResult<CanonicalAddr> {
+    if address.is_empty() || address.trim() != address {
+        return Err(invalid_address(address));
+    }
+    api.canonical_address(&HumanAddr::from(address)).map_err(|_| invalid_address(address))
+}
+
+//The one spelling of address that gets stored in records that have to stay readable, like owners and permission lists
+pub fn normalize<A: Api>(api: &A, address: &str) -> StdResult<String> {
+    let canonical = canonicalize(api, address)?;
+    Ok(api.human_address(&canonical)?.to_string())
+}
+
+//The message sender, spelled the way stored records spell addresses
+pub fn sender_address<A: Api>(api: &A, env: &Env) -> StdResult<String> {
+    normalize(api, env.message.sender.as_str())
+}
+
+fn invalid_address(address: &str) -> StdError {
+    ContractError::InvalidRequest(format!("'{}' is not a valid address", address)).into()
+}
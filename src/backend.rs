@@ -1,22 +1,53 @@
+use std::cmp;
 use std::vec;
 
 use cosmwasm_std::{
-    debug_print, to_binary, Api, Env, Extern, HandleResponse, HumanAddr, Querier, StdError,
+    debug_print, to_binary, Api, CanonicalAddr, Env, Extern, HandleResponse, HumanAddr, Querier, StdError,
     StdResult, Storage,
 };
-use cosmwasm_storage::{bucket, bucket_read};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use secret_toolkit::crypto::sha_256;
 
+use crate::address::{canonicalize, normalize, sender_address};
+use crate::error::ContractError;
+use crate::groups::{grantee_addresses, groups_of, resolve_grantee, validate_grantee};
 use crate::messaging::{ Message, create_empty_collection, append_message, collection_exist, send_message };
-use crate::msg::{FileResponse, HandleAnswer, WalletInfoResponse };
+use crate::msg::{
+    BigTreeResponse, CheckAccessResponse, FileResponse, FolderContentsResponse, HandleAnswer, InheritedPermissions,
+    PermissionsResponse, PublicContentsResponse, ResponseStatus, ScopedKeyInfo, SharedFile, SharedWithMeResponse,
+    StatResponse, VersionContentsResponse, VersionsResponse, ViewingKeysResponse, WalletInfoResponse,
+};
 use crate::nodes::write_claim;
 use crate::ordered_set::OrderedSet;
-use crate::state::{load, write_viewing_key, State, CONFIG_KEY};
+use crate::path::Path;
+use crate::scoped_key::{read_scoped_keys, validate_key_name};
+use crate::state::{
+    load, read_viewing_keys, remove_viewing_key, save, write_viewing_key, State, CONFIG_KEY, DEFAULT_KEY_NAME,
+};
 use crate::viewing_key::ViewingKey;
 
 // Bucket namespace list:
 static WALLET_INFO_LOCATION: &[u8] = b"WALLET_INFO";
+static FOLDER_CHILDREN_LOCATION: &[u8] = b"FOLDER_CHILDREN";
+static FILE_VERSIONS_LOCATION: &[u8] = b"FILE_VERSIONS";
+static LAST_BLOCK_KEY: &[u8] = b"last_block";
+static SHARED_WITH_LOCATION: &[u8] = b"SHARED_WITH";
+
+// GetFolderTree limits, so that a single query can't walk an unbounded subtree
+pub const DEFAULT_TREE_DEPTH: u32 = 4;
+pub const MAX_TREE_DEPTH: u32 = 16;
+pub const DEFAULT_TREE_ENTRIES: u32 = 100;
+pub const MAX_TREE_ENTRIES: u32 = 500;
+
+// How many prior versions of a file are kept, unless the file or its owner's wallet says otherwise
+pub const DEFAULT_VERSION_RETENTION: u32 = 5;
+pub const MAX_VERSION_RETENTION: u32 = 20;
+
+// GetSharedWithMe page sizes
+pub const DEFAULT_SHARED_LIMIT: u32 = 50;
+pub const MAX_SHARED_LIMIT: u32 = 200;
 
 // HandleMsg::InitAddress
 pub fn try_init<S: Storage, A: Api, Q: Querier>(
@@ -33,7 +64,29 @@ pub fn try_init<S: Storage, A: Api, Q: Querier>(
     let mut path = adr.to_string();
     path.push('/');
 
-    let namespace = get_namespace(&deps.storage, &adr).unwrap_or(String::from("namespace does not exist!"));
+    //contents_list holds the root folder's contents followed by one entry per path in path_list
+    if contents_list.len() != path_list.len() + 1 {
+        return Err(ContractError::InvalidRequest(format!(
+            "contents_list needs {} entries, one for the root folder and one for each path in path_list",
+            path_list.len() + 1
+        ))
+        .into());
+    }
+    //entries are created in order, so each one's parent has to be the root or a folder listed before it
+    let mut folders = vec![path.to_string()];
+    for sub_folder in path_list.iter() {
+        let full_path = format!("{}{}", path, sub_folder);
+        Path::parse(&full_path)?;
+        let par_path = parent_path(full_path.to_string());
+        if !folders.contains(&par_path) {
+            return Err(ContractError::InvalidPath(format!("'{}' can't be created. Parent folder: '{}' isn't in path_list before it", full_path, par_path)).into());
+        }
+        if full_path.ends_with('/') {
+            folders.push(full_path);
+        }
+    }
+
+    let namespace = get_namespace(&deps.storage, &deps.api, &adr).unwrap_or(String::from("namespace does not exist!"));
     let already_init = file_exists(&mut deps.storage, &path, &namespace);
 
     match already_init {
@@ -42,30 +95,25 @@ pub fn try_init<S: Storage, A: Api, Q: Querier>(
             //   One of two things could happen:
             //a) They already have a wallet info saved, so we just pull it out and set init to true.
             //b) They don't have a wallet info saved, so may_load will return None, which prompts a return of a default walletinfo that can be altered and saved asap.
-            let loaded_wallet: Result<Option<WalletInfo>, StdError> = bucket(WALLET_INFO_LOCATION, &mut deps.storage).may_load(adr.as_bytes());
-            let unwrapped_wallet = loaded_wallet.expect("Wallet not found."); //Option will always be unwrapped, but providing error message for clarity.
-            let mut returned_wallet = return_wallet(unwrapped_wallet);
-
-            if returned_wallet.namespace == "empty".to_string() {
-                returned_wallet.init = true;
-                let new_namespace = format!("{}{}", adr, 0);
-                returned_wallet.namespace = new_namespace;
-            } else {
-                returned_wallet.init = true;
-            }
+            let loaded_wallet: Option<WalletInfo> = bucket_load_wallet(&deps.storage, &deps.api, &adr)?;
+            let mut returned_wallet = return_wallet(loaded_wallet);
 
-            let bucket_response =
-                bucket(WALLET_INFO_LOCATION, &mut deps.storage).save(adr.as_bytes(), &returned_wallet);
-            match bucket_response {
-                Ok(bucket_response) => bucket_response,
-                Err(e) => panic!("Bucket Error: {}", e),
-            }
+            //a fresh wallet starts at generation 0, one that called ForgetMe carries on from its counter
+            returned_wallet.init = true;
+            returned_wallet.namespace = namespace_for(&deps.api.canonical_address(&ha)?, returned_wallet.counter);
 
-            create_file(deps, adr.to_string(), &path, &contents_list[0]);
+            bucket_save_wallet(&mut deps.storage, &deps.api, &adr, &returned_wallet)?;
+
+            create_file(deps, &path, File::new_folder(&adr, &contents_list[0], &env), &BlockTime::from_env(&env))?;
 
             for i in 0..path_list.len() {
                 let sub_folder = format!("{}{}", path, path_list[i]);
-                create_file(deps, adr.to_string(), &sub_folder, &contents_list[i+1]);
+                let file = if sub_folder.ends_with('/') {
+                    File::new_folder(&adr, &contents_list[i+1], &env)
+                } else {
+                    File::new(&adr, &contents_list[i+1], &env)
+                };
+                create_file(deps, &sub_folder, file, &BlockTime::from_env(&env))?;
             }
 
             // Messaging
@@ -85,7 +133,7 @@ pub fn try_init<S: Storage, A: Api, Q: Querier>(
             let prng_seed = config.prng_seed;
             let key = ViewingKey::new(&env, &prng_seed, (&entropy).as_ref());
             let message_sender = deps.api.canonical_address(&env.message.sender)?;
-            write_viewing_key(&mut deps.storage, &message_sender, &key);
+            write_viewing_key(&mut deps.storage, &message_sender, DEFAULT_KEY_NAME, &key)?;
 
             Ok(HandleResponse {
                 messages: vec![],
@@ -95,7 +143,7 @@ pub fn try_init<S: Storage, A: Api, Q: Querier>(
         }
         true => {
             let error_message = format!("User has already been initiated");
-            Err(StdError::generic_err(error_message))
+            Err(ContractError::AlreadyExists(error_message).into())
         }
     }
 }
@@ -104,21 +152,266 @@ pub fn return_wallet(x: Option<WalletInfo>) -> WalletInfo {
     match x {
         Some(i) => i,//if exists, their wallet init could be false or true, and their namespace is present,
         //If none, it means the user has never called init before, so we return a wallet info that can be altered and saved right away
-        None => WalletInfo { init: false, namespace: "empty".to_string(), counter: 0, message_list_counter: 0 },
+        None => WalletInfo { init: false, namespace: "empty".to_string(), counter: 0, message_list_counter: 0, version_retention: None },
+
+    }
+}
+
+pub fn get_namespace<'a, S: Storage, A: Api>(store: &'a S, api: &A, sender: &String) -> StdResult<String> {
+    Ok(load_wallet(store, api, sender)?.namespace)
+}
+
+pub fn get_counter<'a, S: Storage, A: Api>(store: &'a S, api: &A, sender: &String) -> StdResult<i32> {
+    Ok(load_wallet(store, api, sender)?.counter)
+}
+
+//Wallets are kept under their canonical address. Ones saved before that are still under the address string,
+//and move over the next time they're saved
+pub fn bucket_load_wallet<'a, S: Storage, A: Api>(store: &'a S, api: &A, address: &str) -> StdResult<Option<WalletInfo>> {
+    let owner = canonicalize(api, address)?;
+    let wallet: Option<WalletInfo> = bucket_read(WALLET_INFO_LOCATION, store).may_load(owner.as_slice())?;
+    match wallet {
+        Some(wallet) => Ok(Some(wallet)),
+        None => bucket_read(WALLET_INFO_LOCATION, store).may_load(address.as_bytes()),
+    }
+}
+
+pub fn bucket_save_wallet<'a, S: Storage, A: Api>(store: &'a mut S, api: &A, address: &str, wallet_info: &WalletInfo) -> StdResult<()> {
+    let owner = canonicalize(api, address)?;
+    let mut wallets = bucket(WALLET_INFO_LOCATION, store);
+    wallets.remove(address.as_bytes());
+    wallets.save(owner.as_slice(), wallet_info)
+}
+
+fn load_wallet<'a, S: Storage, A: Api>(store: &'a S, api: &A, address: &str) -> StdResult<WalletInfo> {
+    match bucket_load_wallet(store, api, address)? {
+        Some(wallet_info) => Ok(wallet_info),
+        None => Err(ContractError::NotFound(String::from("Wallet not found.")).into()),
+    }
+}
+
+/*
+  Namespaces are the bucket prefixes a wallet's files, child index and versions live under. They used to be
+  format!("{}{}", address, counter), so alice1 at counter 0 and alice at counter 10 both got alice10.
+  They are now the hex of
+    NAMESPACE_LAYOUT_VERSION | length of the canonical address (2 bytes) | canonical address | counter (4 bytes)
+  every part of which is either fixed width or length prefixed, so no two wallets or generations can share one.
+  Legacy namespaces start with a secret1... address, which hex can't spell, so the two layouts can't collide either.
+  Wallets still on the old layout keep the namespace saved in their WalletInfo until MigrateStorage moves them.
+*/
+pub const NAMESPACE_LAYOUT_VERSION: u8 = 1;
+
+pub fn namespace_for(owner: &CanonicalAddr, counter: i32) -> String {
+    let owner = owner.as_slice();
+    let mut key: Vec<u8> = Vec::with_capacity(owner.len() + 7);
+    key.push(NAMESPACE_LAYOUT_VERSION);
+    key.extend_from_slice(&(owner.len() as u16).to_be_bytes());
+    key.extend_from_slice(owner);
+    key.extend_from_slice(&(counter as u32).to_be_bytes());
+    hex::encode(key)
+}
+
+/*
+  Records as the first release saved them. Folders were only told apart by their path, and there was no child
+  index, metadata or versions. Wallets from then still hold these until MigrateStorage converts them.
+*/
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct LegacyFile {
+    contents: String,
+    owner: String,
+    public: bool,
+    allow_read_list: OrderedSet<String>,
+    allow_write_list: OrderedSet<String>,
+}
+
+impl LegacyFile {
+    //Nothing recorded when a legacy record was made, so it's dated to the migration. Addresses are normalized like
+    //new ones are: grants that don't name a valid address are dropped, and an owner that doesn't falls back to the
+    //wallet the record lives under
+    fn upgrade<A: Api>(self, api: &A, path: &String, env: &Env) -> File {
+        let owner = match normalize(api, &self.owner) {
+            Ok(owner) => owner,
+            Err(_) => Path::parse(path).map(|p| p.root().to_string()).unwrap_or(self.owner.to_string()),
+        };
+        let mut file = if path.ends_with('/') {
+            File::new_folder(&owner, &self.contents, env)
+        } else {
+            File::new(&owner, &self.contents, env)
+        };
+        file.public = self.public;
+        for address in self.allow_read_list.to_vec().iter() {
+            if let Ok(address) = normalize(api, address) {
+                file.allow_read(address, None);
+            }
+        }
+        for address in self.allow_write_list.to_vec().iter() {
+            if let Ok(address) = normalize(api, address) {
+                file.allow_write(address, None);
+            }
+        }
+        file
+    }
+}
+
+enum StoredFile {
+    Current(File),
+    Legacy(LegacyFile),
+}
+
+//The record at path in whichever shape it was saved in
+fn load_stored_file<'a, S: Storage>(store: &'a S, path: &String, namespace: &String) -> StdResult<Option<StoredFile>> {
+    match bucket_read::<S, File>(namespace.as_bytes(), store).may_load(path.as_bytes()) {
+        Ok(file) => Ok(file.map(StoredFile::Current)),
+        Err(_) => Ok(bucket_read::<S, LegacyFile>(namespace.as_bytes(), store)
+            .may_load(path.as_bytes())?
+            .map(StoredFile::Legacy)),
+    }
+}
+
+//How deep path is below its root folder, so parents can be handled before their children
+fn path_depth(path: &String) -> usize {
+    path.trim_end_matches('/').matches('/').count()
+}
+
+/*
+  Moves root and everything below it from one namespace to another: records, child index and versions.
+  Records still in the legacy shape are converted on the way. Those were saved without a child index, so the ones
+  below root can't be found by walking it and have to be named in legacy_paths; each is filed under its parent,
+  which has to be root, already moved, or named as well. A path that's already in to is skipped.
+  The shared-with-me index holds paths, not namespaces, so only converted records, whose grants were never indexed, add to it.
+*/
+fn migrate_namespace<S: Storage, A: Api>(
+    store: &mut S,
+    api: &A,
+    env: &Env,
+    root: &String,
+    legacy_paths: &[String],
+    from: &String,
+    to: &String,
+) -> StdResult<()> {
+    let now = BlockTime::from_env(&env);
+    let mut entries = collect_subtree(store, root, from)?;
+    let mut listed: Vec<String> = Vec::new();
+    for path in legacy_paths.iter() {
+        if !entries.contains(path) && !listed.contains(path) {
+            listed.push(path.to_string());
+        }
+    }
+    listed.sort_by_key(path_depth);
+    let subtree_len = entries.len();
+    entries.extend(listed);
+
+    for (i, entry) in entries.iter().enumerate() {
+        match load_stored_file(store, entry, from)? {
+            Some(StoredFile::Legacy(legacy)) => {
+                bucket_save_file(store, entry, &legacy.upgrade(api, entry, env), to, &now)?;
+                bucket_remove_file(store, entry, from);
+            }
+            Some(StoredFile::Current(file)) => {
+                bucket(to.as_bytes(), store).save(entry.as_bytes(), &file)?;
+                bucket_remove_file(store, entry, from);
+            }
+            None if i >= subtree_len => {
+                if bucket_load_readonly_file(store, entry, to).is_ok() {
+                    continue;
+                }
+                return Err(ContractError::NotFound(format!("Migration unsuccessful. '{}' doesn't exist", entry)).into());
+            }
+            None => {}
+        }
+
+        let children = bucket_load_children(store, entry, from)?;
+        if children.len() > 0 {
+            bucket_save_children(store, entry, &children, to)?;
+            bucket_save_children(store, entry, &OrderedSet::new(), from)?;
+        }
+
+        let versions = bucket_load_versions(store, entry, from)?;
+        if !versions.is_empty() {
+            bucket_save_versions(store, entry, &versions, to)?;
+            bucket_save_versions(store, entry, &vec![], from)?;
+        }
 
+        if i >= subtree_len {
+            let par_path = parent_path(entry.to_string());
+            match bucket_load_readonly_file(store, &par_path, to) {
+                Ok(parent) if parent.is_folder() => bucket_add_child(store, &par_path, entry, to)?,
+                _ => return Err(ContractError::InvalidPath(format!("Migration unsuccessful. Parent folder: '{}' of '{}' wasn't migrated, list it too", par_path, entry)).into()),
+            }
+        }
     }
+    Ok(())
 }
 
-pub fn get_namespace<'a, S: Storage>(store: &'a S, sender: &String) -> StdResult<String> {
-    let loaded_wallet: Result<WalletInfo, StdError> = bucket_read(WALLET_INFO_LOCATION, store).load(sender.as_bytes());
-    let unwrapped_wallet = loaded_wallet?;
-    Ok(unwrapped_wallet.namespace)
+//Moves the sender's wallet onto the current storage layout: its wallet info under its canonical address and its files
+//onto the current namespace layout, converted from the legacy record shape where needed. paths names the sender's
+//legacy files and folders below their root, which storage can't list. Calling it again, say for paths left out the
+//first time, picks up where the last call stopped
+pub fn try_migrate_storage<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    paths: Vec<String>,
+) -> StdResult<HandleResponse> {
+    let adr = sender_address(&deps.api, &env)?;
+    let mut wallet_info = load_wallet(&deps.storage, &deps.api, &adr)?;
+
+    let root = format!("{}/", adr);
+    for path in paths.iter() {
+        Path::parse(path)?;
+        if !path.starts_with(&root) || *path == root {
+            return Err(ContractError::InvalidPath(format!("Migration unsuccessful. '{}' isn't inside of your root folder", path)).into());
+        }
+    }
+
+    //a wallet that's already on the current layout can still have legacy records left where the old layout put them
+    let namespace = namespace_for(&canonicalize(&deps.api, &adr)?, wallet_info.counter);
+    let legacy = if wallet_info.namespace != namespace {
+        wallet_info.namespace.to_string()
+    } else {
+        format!("{}{}", adr, wallet_info.counter)
+    };
+    if wallet_info.init {
+        migrate_namespace(&mut deps.storage, &deps.api, &env, &root, &paths, &legacy, &namespace)?;
+    }
+    wallet_info.namespace = namespace;
+    bucket_save_wallet(&mut deps.storage, &deps.api, &adr, &wallet_info)?;
+
+    Ok(HandleResponse::default())
 }
 
-pub fn get_counter<'a, S: Storage>(store: &'a S, sender: &String) -> StdResult<i32> {
-    let loaded_wallet: Result<WalletInfo, StdError> = bucket_read(WALLET_INFO_LOCATION, store).load(sender.as_bytes());
-    let unwrapped_wallet = loaded_wallet?;
-    Ok(unwrapped_wallet.counter)
+//Converts the legacy records at path and at every folder above it in place, so handles on a wallet that hasn't been
+//through MigrateStorage find them in the current shape. Each converted record is filed under its parent, which
+//rebuilds the child index one touched path at a time
+pub fn upgrade_legacy_path<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: &String,
+) -> StdResult<()> {
+    let now = BlockTime::from_env(&env);
+    let namespace = match get_namespace_from_path(deps, path) {
+        Ok(namespace) => namespace,
+        Err(_) => return Ok(()),
+    };
+
+    let mut entries: Vec<String> = Vec::new();
+    let mut next = Some(Path::parse(path)?);
+    while let Some(entry) = next {
+        entries.push(entry.to_string());
+        next = entry.parent();
+    }
+
+    for entry in entries.iter().rev() {
+        if let Some(StoredFile::Legacy(legacy)) = load_stored_file(&deps.storage, entry, &namespace)? {
+            bucket_save_file(&mut deps.storage, entry, &legacy.upgrade(&deps.api, entry, env), &namespace, &now)?;
+            let par_path = parent_path(entry.to_string());
+            if let Ok(parent) = bucket_load_readonly_file(&deps.storage, &par_path, &namespace) {
+                if parent.is_folder() {
+                    bucket_add_child(&mut deps.storage, &par_path, entry, &namespace)?;
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 pub fn try_forget_me<S: Storage, A: Api, Q: Querier>(
@@ -130,20 +423,22 @@ pub fn try_forget_me<S: Storage, A: Api, Q: Querier>(
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
     let adr = String::from(ha.as_str());
 
-    let load_bucket: Result<WalletInfo, StdError> =
-        bucket_read(WALLET_INFO_LOCATION, &deps.storage).load(adr.as_bytes());
-    let mut wallet_info = load_bucket?;
+    let mut wallet_info = load_wallet(&deps.storage, &deps.api, &adr)?;
+
+    //the old namespace is left behind, so nothing in it should show up as shared with anyone anymore
+    let root = format!("{}/", adr);
+    for entry in collect_subtree(&deps.storage, &root, &wallet_info.namespace)?.iter() {
+        if let Ok(f) = bucket_load_readonly_file(&deps.storage, entry, &wallet_info.namespace) {
+            sync_shared_index(&mut deps.storage, entry, &f.grantees(), &Vec::new())?;
+        }
+    }
 
     wallet_info.init = false;
     let new_counter = wallet_info.counter + 1;
     wallet_info.counter = new_counter;
-    let new_namespace = format!("{}{}", adr, new_counter);
-    wallet_info.namespace = new_namespace;
+    wallet_info.namespace = namespace_for(&deps.api.canonical_address(&ha)?, new_counter);
 
-    bucket(WALLET_INFO_LOCATION, &mut deps.storage)
-        .save(ha.as_str().as_bytes(), &wallet_info)
-        .map_err(|err| println!("{:?}", err))
-        .ok();
+    bucket_save_wallet(&mut deps.storage, &deps.api, &adr, &wallet_info)?;
 
     Ok(HandleResponse::default())
 }
@@ -152,16 +447,15 @@ pub fn try_you_up_bro<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     address: String,
 ) -> StdResult<WalletInfoResponse> {
-    let load_bucket: Result<WalletInfo, StdError> =
-        bucket_read(WALLET_INFO_LOCATION, &deps.storage).load(address.as_bytes());
+    let load_bucket = bucket_load_wallet(&deps.storage, &deps.api, &address)?;
 
     match load_bucket {
-        Ok(wallet_info) => Ok(WalletInfoResponse {
+        Some(wallet_info) => Ok(WalletInfoResponse {
             init: wallet_info.init,
             namespace: wallet_info.namespace,
             counter: wallet_info.counter
         }),
-        Err(_e) => Ok(WalletInfoResponse {
+        None => Ok(WalletInfoResponse {
             init: false,
             namespace: String::from("empty"),
             counter: 0
@@ -169,10 +463,12 @@ pub fn try_you_up_bro<S: Storage, A: Api, Q: Querier>(
     }
 }
 
+//A wallet can hold several keys, one per name. Creating or setting a key only replaces the key with the same name
 pub fn try_create_viewing_key<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     entropy: String,
+    name: Option<String>,
 ) -> StdResult<HandleResponse> {
     let config: State = load(&mut deps.storage, CONFIG_KEY)?;
     let prng_seed = config.prng_seed;
@@ -181,7 +477,9 @@ pub fn try_create_viewing_key<S: Storage, A: Api, Q: Querier>(
 
     let message_sender = deps.api.canonical_address(&env.message.sender)?;
 
-    write_viewing_key(&mut deps.storage, &message_sender, &key);
+    let name = name.unwrap_or(String::from(DEFAULT_KEY_NAME));
+    validate_key_name(&name)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &name, &key)?;
 
     Ok(HandleResponse {
         messages: vec![],
@@ -190,16 +488,139 @@ pub fn try_create_viewing_key<S: Storage, A: Api, Q: Querier>(
     })
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
-enum PermType {
+pub fn try_set_viewing_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    key: String,
+    name: Option<String>,
+) -> StdResult<HandleResponse> {
+    if key.is_empty() {
+        return Err(ContractError::InvalidRequest(String::from("Viewing key can't be empty")).into());
+    }
+
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+
+    let name = name.unwrap_or(String::from(DEFAULT_KEY_NAME));
+    validate_key_name(&name)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &name, &ViewingKey(key))?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::SetViewingKey { status: ResponseStatus::Success })?),
+    })
+}
+
+pub fn try_revoke_viewing_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+
+    if !remove_viewing_key(&mut deps.storage, &message_sender, &name)? {
+        return Err(ContractError::NotFound(format!("Viewing key {}", name)).into());
+    }
+
+    Ok(HandleResponse::default())
+}
+
+//The names of behalf's viewing keys, and what each scoped key is limited to. The keys themselves are never shown
+pub fn query_viewing_keys<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    behalf: &HumanAddr,
+) -> StdResult<ViewingKeysResponse> {
+    let owner = deps.api.canonical_address(behalf)?;
+
+    let keys = read_viewing_keys(&deps.storage, &owner)?
+        .into_iter()
+        .map(|k| k.name)
+        .collect();
+    let scoped_keys = read_scoped_keys(&deps.storage, &owner)?
+        .into_iter()
+        .map(|k| ScopedKeyInfo { name: k.name, path_prefix: k.path_prefix, permissions: k.permissions, expires: k.expires })
+        .collect();
+
+    Ok(ViewingKeysResponse { keys, scoped_keys })
+}
+
+// Permission levels, lowest first. Every level includes the ones below it:
+//   READ     see the contents
+//   COMMENT  read, and send the owner comments on it
+//   WRITE    change the contents, or add to a folder
+//   SHARE    grant READ up to SHARE to other addresses, and take away READ and WRITE grants
+//   MANAGE   take away any grant, move, remove, reset permissions and change the owner. Owners always hold this level
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+pub enum PermType {
     READ,
+    COMMENT,
     WRITE,
+    SHARE,
+    MANAGE,
+}
+
+// When a grant stops counting. The block height or time is compared against the block the contract last saw
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    AtHeight(u64),
+    AtTime(u64),
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+impl Expiration {
+    pub fn is_expired(&self, now: &BlockTime) -> bool {
+        match self {
+            Expiration::AtHeight(height) => now.height >= *height,
+            Expiration::AtTime(time) => now.time >= *time,
+        }
+    }
+}
+
+// A single grant on a file. Without an expiry it lasts until it is taken away
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
 pub struct PermissionBlock {
-    address: String,
-    permission_type: PermType,
+    pub address: String,
+    pub permission_type: PermType,
+    pub expires: Option<Expiration>,
+}
+
+impl PermissionBlock {
+    pub fn is_live(&self, now: &BlockTime) -> bool {
+        match &self.expires {
+            Some(expiration) => !expiration.is_expired(now),
+            None => true,
+        }
+    }
+}
+
+//Handles can see the current block and check expiries against it, queries can't. Every handle records its block
+//here so that queries can judge expiries against the latest block the contract has seen, which is behind on a
+//contract nobody has sent a message to for a while. See QueryMsg
+pub fn save_last_block<S: Storage>(store: &mut S, env: &Env) -> StdResult<()> {
+    save(store, LAST_BLOCK_KEY, &BlockTime::from_env(env))
+}
+
+pub fn last_block<S: Storage>(store: &S) -> BlockTime {
+    load(store, LAST_BLOCK_KEY).unwrap_or(BlockTime { height: 0, time: 0 })
+}
+
+//Sends message to the address, or to every member if the grantee is a group
+fn notify_grantee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    grantee: &String,
+    message: &String,
+) -> StdResult<()> {
+    for address in grantee_addresses(&deps.storage, grantee) {
+        let recipient = HumanAddr::from(address);
+        let sent_message = send_message(deps, env, recipient, message);
+
+        match sent_message{
+            Ok(_) => (),
+            Err(_) => return Err(ContractError::NotFound(String::from("recipient does not exist")).into()),
+        }
+    }
+    Ok(())
 }
 
 pub fn try_allow_write<S: Storage, A: Api, Q: Querier>(
@@ -208,7 +629,9 @@ pub fn try_allow_write<S: Storage, A: Api, Q: Querier>(
     path: String,
     message: String,
     address_list: Vec<String>,
+    expires: Option<Expiration>,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
         .api
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -216,25 +639,19 @@ pub fn try_allow_write<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to allow write"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::SHARE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to allow write")).into());
     }
 
     for i in 0..address_list.len() {
-        let address = &address_list[i];
-        f.allow_write(address.to_string());
-
-        let recipient = HumanAddr::from(String::from(address));
-        let sent_message = send_message(deps, &env, recipient , &message);
+        let address = &resolve_grantee(&deps.api, &deps.storage, &signer.to_string(), f.get_owner(), &address_list[i])?;
+        f.allow_write(address.to_string(), expires.clone());
 
-        match sent_message{
-            Ok(_) => (),
-            Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-        }
+        notify_grantee(deps, &env, address, &message)?;
         //before, bucket_save_file was right here inside of the for loop, but that would mean calling a save every single loop--which is extremely expensive
         //We declared f as mutable above, so in every loop, the file is being mutated by .allow_write, which means we need only call bucket_save_file once after this loop is done
     }
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
 
     Ok(HandleResponse::default())
 }
@@ -247,6 +664,7 @@ pub fn try_disallow_write<S: Storage, A: Api, Q: Querier>(
     notify: bool,
     address_list: Vec<String>,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
         .api
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -254,25 +672,19 @@ pub fn try_disallow_write<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to disallow write"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::SHARE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to disallow write")).into());
     }
 
     for i in 0..address_list.len() {
-        let address = &address_list[i];
+        let address = &validate_grantee(&deps.api, &deps.storage, &signer.to_string(), &address_list[i])?;
         f.disallow_write(address.to_string());
 
         if notify == true {
-            let recipient = HumanAddr::from(String::from(address));
-            let sent_message = send_message(deps, &env, recipient , &message);
-    
-            match sent_message{
-                Ok(_) => (),
-                Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-            }
+            notify_grantee(deps, &env, address, &message)?;
         }
     } //moved bucket_save_file outside of for loop to save gas. This is also done for allow_read and disallow_read
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
 
     Ok(HandleResponse::default())
 }
@@ -284,6 +696,7 @@ pub fn try_reset_write<S: Storage, A: Api, Q: Querier>(
     message: String,
     notify: bool
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
     .api
     .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -291,26 +704,20 @@ pub fn try_reset_write<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to reset write list"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to reset write list")).into());
     }
 
     if notify == true {
-        let address_list = f.allow_write_list.to_vec();
+        let address_list = f.addresses_from(&PermType::WRITE);
         for i in 0..address_list.len() {
             let address = &address_list[i];
-            let recipient = HumanAddr::from(String::from(address));
-            let sent_message = send_message(deps, &env, recipient , &message);
-    
-            match sent_message{
-                Ok(_) => (),
-                Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-            }
+            notify_grantee(deps, &env, address, &message)?;
         }
     }
     
-    f.allow_write_list = OrderedSet::new();
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    f.reset(&PermType::WRITE);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
     Ok(HandleResponse::default())
 }
 
@@ -320,7 +727,9 @@ pub fn try_allow_read<S: Storage, A: Api, Q: Querier>(
     path: String,
     message: String,
     address_list: Vec<String>,
+    expires: Option<Expiration>,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
     .api
     .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -328,23 +737,17 @@ pub fn try_allow_read<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?; 
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to allow read"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::SHARE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to allow read")).into());
     }
 
     for i in 0..address_list.len() {
-        let address = &address_list[i];
-        f.allow_read(address.to_string());
-
-        let recipient = HumanAddr::from(String::from(address));
-        let sent_message = send_message(deps, &env, recipient , &message);
+        let address = &resolve_grantee(&deps.api, &deps.storage, &signer.to_string(), f.get_owner(), &address_list[i])?;
+        f.allow_read(address.to_string(), expires.clone());
 
-        match sent_message{
-            Ok(_) => (),
-            Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-        }
+        notify_grantee(deps, &env, address, &message)?;
     }
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
     Ok(HandleResponse::default())
 
 }
@@ -357,6 +760,7 @@ pub fn try_disallow_read<S: Storage, A: Api, Q: Querier>(
     notify: bool,
     address_list: Vec<String>,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
         .api
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -364,26 +768,20 @@ pub fn try_disallow_read<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to disallow read"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::SHARE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to disallow read")).into());
     }
 
     for i in 0..address_list.len() {
-        let address = &address_list[i];
+        let address = &validate_grantee(&deps.api, &deps.storage, &signer.to_string(), &address_list[i])?;
         f.disallow_read(address.to_string());
 
         if notify == true {
-            let recipient = HumanAddr::from(String::from(address));
-            let sent_message = send_message(deps, &env, recipient , &message);
-    
-            match sent_message{
-                Ok(_) => (),
-                Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-            }
+            notify_grantee(deps, &env, address, &message)?;
         }
     }
     
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
     Ok(HandleResponse::default())
 }
 
@@ -394,6 +792,7 @@ pub fn try_reset_read<S: Storage, A: Api, Q: Querier>(
     message: String,
     notify: bool
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
     .api
     .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
@@ -401,142 +800,424 @@ pub fn try_reset_read<S: Storage, A: Api, Q: Querier>(
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
     let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    if !f.can_write(signer.to_string()) {
-        return Err(StdError::generic_err("Unauthorized to reset read list"));
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to reset read list")).into());
     }
 
     if notify == true {
-        let address_list = f.allow_read_list.to_vec();
+        let address_list = f.addresses_from(&PermType::READ);
         for i in 0..address_list.len() {
             let address = &address_list[i];
-            let recipient = HumanAddr::from(String::from(address));
-            let sent_message = send_message(deps, &env, recipient , &message);
-    
-            match sent_message{
-                Ok(_) => (),
-                Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-            }
+            notify_grantee(deps, &env, address, &message)?;
         }
     }
 
-    f.allow_read_list = OrderedSet::new();
-    bucket_save_file(&mut deps.storage, &path, &f, &namespace);
+    f.reset(&PermType::READ);
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
     Ok(HandleResponse::default())
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
-pub struct WalletInfo {
-    pub init: bool,
-    pub namespace: String,
-    pub counter: i32,
-    pub message_list_counter: i32
-}
-
-// HandleMsg FILE
-#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
-pub struct File {
-    contents: String,
-    owner: String,
-    public: bool,
-    allow_read_list: OrderedSet<String>,
-    allow_write_list: OrderedSet<String>,
-}
-
-impl File {
-
-    pub fn new(owner: &str, contents: &str) -> File {
-        File {
-            contents: String::from(contents),
-            owner: String::from(owner),
-            public: false,
-            allow_read_list: OrderedSet::<String>::new(),
-            allow_write_list: OrderedSet::<String>::new(),
-        }
-    }
+//Grants level on path to every address in address_list. Sharing takes SHARE, and nobody can hand out more than they hold
+pub fn try_grant<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+    message: String,
+    address_list: Vec<String>,
+    level: PermType,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let signer = deps
+        .api
+        .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
 
-    pub fn get_contents(&self) -> &str {
-        &self.contents
-    }
+    let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-    /**
-      Please call these before doing anything to files. If you are adding a newly
-      created file to a folder, please check that you can write to the folder. If
-      the file exists, just check the file permission since they overwrite the
-      folder.
-    */
-    pub fn can_read(&self, address: String) -> bool {
-        if self.owner.eq(&address) {
-            return true;
-        }
-        if self.public {
-            return true;
-        }
-        for i in 0..self.allow_read_list.len() {
-            if String::from(self.allow_read_list.get(i).unwrap()).eq(&address) {
-                return true;
-            }
-        }
-        for i in 0..self.allow_write_list.len() {
-            if String::from(self.allow_write_list.get(i).unwrap()).eq(&address) {
-                return true;
+    let held = permission_level(&deps.storage, &path, &namespace, &f, &signer.to_string(), &now);
+    match held {
+        Some(held) if held >= PermType::SHARE => {
+            if level > held {
+                return Err(ContractError::Unauthorized(String::from("Unauthorized to grant a higher level than your own")).into());
             }
         }
-
-        false
+        _ => return Err(ContractError::Unauthorized(String::from("Unauthorized to grant permissions")).into()),
     }
 
-    pub fn can_write(&self, address: String) -> bool {
-        if self.owner.eq(&address) {
-            return true;
-        }
-        for i in 0..self.allow_write_list.len() {
-            if String::from(self.allow_write_list.get(i).unwrap()).eq(&address) {
-                return true;
-            }
-        }
-        false
+    for i in 0..address_list.len() {
+        let address = &resolve_grantee(&deps.api, &deps.storage, &signer.to_string(), f.get_owner(), &address_list[i])?;
+        f.grant(address.to_string(), level.clone(), expires.clone());
+
+        notify_grantee(deps, &env, address, &message)?;
     }
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
+    Ok(HandleResponse::default())
+}
 
-    pub fn allow_read(&mut self, address: String) -> bool {
-        if self.owner.eq(&address) {
-            return false;
-        }
+//Takes away every level the addresses hold on path itself. Grants on the folders above are left alone
+pub fn try_revoke<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+    message: String,
+    notify: bool,
+    address_list: Vec<String>,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let signer = deps
+        .api
+        .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
 
-        self.allow_read_list.push(address);
+    let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-        true
+    if !has_permission(&deps.storage, &path, &namespace, &f, signer.to_string(), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to revoke permissions")).into());
     }
 
-    pub fn allow_write(&mut self, address: String) -> bool {
-        if self.owner.eq(&address) {
-            return false;
-        }
-
-        self.allow_write_list.push(address);
+    for i in 0..address_list.len() {
+        let address = &validate_grantee(&deps.api, &deps.storage, &signer.to_string(), &address_list[i])?;
+        f.revoke_all(address);
 
-        true
+        if notify == true {
+            notify_grantee(deps, &env, address, &message)?;
+        }
     }
 
-    pub fn disallow_read(&mut self, address: String) -> bool {
-        if self.owner.eq(&address) {
-            return false;
-        }
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
+    Ok(HandleResponse::default())
+}
 
-        self.allow_read_list.remove(address);
+//Sends the owner of path a comment on it, which takes COMMENT
+pub fn try_comment<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+    contents: String,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
 
-        true
+    if !has_permission(&deps.storage, &path, &namespace, &f, sender_address(&deps.api, &env)?, PermType::COMMENT, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to comment")).into());
     }
 
-    pub fn disallow_write(&mut self, address: String) -> bool {
+    let comment = format!("Comment on [ {} ]: {}", path, contents);
+    send_message(deps, &env, HumanAddr::from(f.get_owner()), &comment)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_make_public<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+) -> StdResult<HandleResponse> {
+    set_public(deps, env, path, true)
+}
+
+pub fn try_make_private<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+) -> StdResult<HandleResponse> {
+    set_public(deps, env, path, false)
+}
+
+//Public files can be read by anyone, with or without a viewing key, so flipping the flag takes MANAGE
+fn set_public<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+    public: bool,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
+
+    if !has_permission(&deps.storage, &path, &namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to change whether this file is public")).into());
+    }
+
+    if public {
+        f.make_public();
+    } else {
+        f.make_private();
+    }
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
+    Ok(HandleResponse::default())
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct WalletInfo {
+    pub init: bool,
+    pub namespace: String,
+    pub counter: i32,
+    pub message_list_counter: i32,
+    //how many prior versions to keep for files that don't set their own retention
+    #[serde(default)]
+    pub version_retention: Option<u32>,
+}
+
+// What a record in the file tree is. Only folders can have children
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Folder,
+    File,
+}
+
+// Block height and time of the handle that touched a record
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct BlockTime {
+    pub height: u64,
+    pub time: u64,
+}
+
+impl BlockTime {
+    pub fn from_env(env: &Env) -> BlockTime {
+        BlockTime {
+            height: env.block.height,
+            time: env.block.time,
+        }
+    }
+}
+
+// Everything a client needs to know about a file without downloading its contents
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct FileMetadata {
+    pub version: u32,
+    pub modified_by: String,
+    pub size: u64,
+    pub created_at: BlockTime,
+    pub modified_at: BlockTime,
+    pub content_type: Option<String>,
+    //hex encoded SHA-256 of the contents
+    pub checksum: String,
+}
+
+// HandleMsg FILE
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct File {
+    kind: NodeKind,
+    contents: String,
+    owner: String,
+    public: bool,
+    permissions: OrderedSet<PermissionBlock>,
+    metadata: FileMetadata,
+    //how many prior versions to keep. None falls back to the owner's wallet setting
+    retention: Option<u32>,
+    //whether the permission lists of the folders above this record apply to it as well
+    inherit: bool,
+    //who ownership has been offered to. The owner only changes once they accept
+    pending_owner: Option<String>,
+}
+
+// A prior version of a file, as it was right before it was overwritten
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct FileVersion {
+    pub contents: String,
+    pub metadata: FileMetadata,
+}
+
+impl File {
+
+    pub fn new(owner: &str, contents: &str, env: &Env) -> File {
+        File {
+            kind: NodeKind::File,
+            contents: String::from(contents),
+            owner: String::from(owner),
+            public: false,
+            permissions: OrderedSet::<PermissionBlock>::new(),
+            metadata: FileMetadata {
+                version: 1,
+                modified_by: String::from(owner),
+                size: contents.len() as u64,
+                created_at: BlockTime::from_env(env),
+                modified_at: BlockTime::from_env(env),
+                content_type: None,
+                checksum: hex::encode(sha_256(contents.as_bytes())),
+            },
+            retention: None,
+            inherit: true,
+            pending_owner: None,
+        }
+    }
+
+    pub fn new_folder(owner: &str, contents: &str, env: &Env) -> File {
+        File {
+            kind: NodeKind::Folder,
+            ..File::new(owner, contents, env)
+        }
+    }
+
+    pub fn with_content_type(mut self, content_type: Option<String>) -> File {
+        self.metadata.content_type = content_type;
+        self
+    }
+
+    pub fn get_metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    //Replaces the contents and bumps the version. Owner, permissions and created_at stay as they were
+    pub fn update_contents(&mut self, author: &str, contents: &str, content_type: Option<String>, env: &Env) {
+        self.contents = String::from(contents);
+        self.metadata.version += 1;
+        self.metadata.modified_by = String::from(author);
+        self.metadata.size = contents.len() as u64;
+        self.metadata.modified_at = BlockTime::from_env(env);
+        self.metadata.checksum = hex::encode(sha_256(contents.as_bytes()));
+        if content_type.is_some() {
+            self.metadata.content_type = content_type;
+        }
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.kind == NodeKind::Folder
+    }
+
+    pub fn get_contents(&self) -> &str {
+        &self.contents
+    }
+
+    pub fn get_owner(&self) -> &str {
+        &self.owner
+    }
+
+    /**
+      Please call these before doing anything to files. If you are adding a newly
+      created file to a folder, please check that you can write to the folder. If
+      the file exists, just check the file permission since they overwrite the
+      folder.
+    */
+    //groups are the references of every group address is a member of, see groups::groups_of
+    pub fn can_read(&self, address: String, groups: &[String], now: &BlockTime) -> bool {
+        self.public || self.has_level(&address, groups, PermType::READ, now)
+    }
+
+    pub fn can_write(&self, address: String, groups: &[String], now: &BlockTime) -> bool {
+        self.has_level(&address, groups, PermType::WRITE, now)
+    }
+
+    pub fn has_level(&self, address: &String, groups: &[String], level: PermType, now: &BlockTime) -> bool {
+        match self.level(address, groups, now) {
+            Some(held) => held >= level,
+            None => false,
+        }
+    }
+
+    //The highest level address holds on this record alone, directly or through a group
+    pub fn level(&self, address: &String, groups: &[String], now: &BlockTime) -> Option<PermType> {
+        if self.owner.eq(address) {
+            return Some(PermType::MANAGE);
+        }
+        self.listed_level(address, groups, now)
+    }
+
+    //Only the grants, which is what a folder passes on to everything inside of it
+    fn listed_level(&self, address: &String, groups: &[String], now: &BlockTime) -> Option<PermType> {
+        self.permissions.to_vec().iter()
+            .filter(|p| (p.address.eq(address) || groups.contains(&p.address)) && p.is_live(now))
+            .map(|p| p.permission_type.clone())
+            .max()
+    }
+
+    pub fn inherits(&self) -> bool {
+        self.inherit
+    }
+
+    pub fn allow_read(&mut self, address: String, expires: Option<Expiration>) -> bool {
+        self.grant(address, PermType::READ, expires)
+    }
+
+    pub fn allow_write(&mut self, address: String, expires: Option<Expiration>) -> bool {
+        self.grant(address, PermType::WRITE, expires)
+    }
+
+    //Granting a level again replaces the earlier grant, so an expiry can be extended or removed
+    pub fn grant(&mut self, address: String, permission_type: PermType, expires: Option<Expiration>) -> bool {
         if self.owner.eq(&address) {
             return false;
         }
 
-        self.allow_write_list.remove(address);
+        self.permissions.retain(|p| !(p.address == address && p.permission_type == permission_type));
+        self.permissions.push(PermissionBlock { address, permission_type, expires });
 
         true
     }
 
+    pub fn disallow_read(&mut self, address: String) -> bool {
+        self.revoke(address, PermType::READ)
+    }
+
+    pub fn disallow_write(&mut self, address: String) -> bool {
+        self.revoke(address, PermType::WRITE)
+    }
+
+    pub fn revoke(&mut self, address: String, permission_type: PermType) -> bool {
+        if self.owner.eq(&address) {
+            return false;
+        }
+
+        self.permissions.retain(|p| !(p.address == address && p.permission_type == permission_type));
+
+        true
+    }
+
+    //Every address holding a grant of permission_type, expired or not
+    pub fn addresses(&self, permission_type: &PermType) -> Vec<String> {
+        self.permissions.to_vec().iter()
+            .filter(|p| p.permission_type == *permission_type)
+            .map(|p| p.address.to_string())
+            .collect()
+    }
+
+    //The grants that haven't expired yet
+    pub fn live_permissions(&self, now: &BlockTime) -> Vec<PermissionBlock> {
+        self.permissions.to_vec().iter()
+            .filter(|p| p.is_live(now))
+            .cloned()
+            .collect()
+    }
+
+    //Every address or group holding at least one grant, expired or not
+    pub fn grantees(&self) -> Vec<String> {
+        let mut grantees: Vec<String> = Vec::new();
+        for p in self.permissions.to_vec().iter() {
+            if !grantees.contains(&p.address) {
+                grantees.push(p.address.to_string());
+            }
+        }
+        grantees
+    }
+
+    //Takes away every grant address holds, whatever the level
+    pub fn revoke_all(&mut self, address: &String) {
+        self.permissions.retain(|p| p.address != *address);
+    }
+
+    //Every address or group holding a grant of permission_type or above, expired or not
+    pub fn addresses_from(&self, permission_type: &PermType) -> Vec<String> {
+        let mut addresses: Vec<String> = Vec::new();
+        for p in self.permissions.to_vec().iter().filter(|p| p.permission_type >= *permission_type) {
+            if !addresses.contains(&p.address) {
+                addresses.push(p.address.to_string());
+            }
+        }
+        addresses
+    }
+
+    //Takes away every grant of permission_type and the levels above it, since those include it
+    pub fn reset(&mut self, permission_type: &PermType) {
+        self.permissions.retain(|p| p.permission_type < *permission_type);
+    }
+
+    pub fn drop_expired(&mut self, now: &BlockTime) {
+        self.permissions.retain(|p| p.is_live(now));
+    }
+
     pub fn make_public(&mut self) -> bool {
         self.public = true;
         true
@@ -551,10 +1232,108 @@ impl File {
         self.public
     }
 
-    pub fn change_owner(&mut self, new_owner: String) {
-        self.owner = new_owner;
+    pub fn propose_owner(&mut self, new_owner: String) {
+        self.pending_owner = Some(new_owner);
     }
 
+    pub fn pending_owner(&self) -> Option<&String> {
+        self.pending_owner.as_ref()
+    }
+
+    pub fn clear_pending_owner(&mut self) {
+        self.pending_owner = None;
+    }
+
+    //Hands the file to the pending owner, if there is one
+    pub fn accept_ownership(&mut self) -> bool {
+        match self.pending_owner.take() {
+            Some(new_owner) => {
+                self.owner = new_owner;
+                true
+            }
+            None => false,
+        }
+    }
+
+    //Clears both permission lists and makes the file private again. The owner is left alone
+    pub fn drop_acl(&mut self) {
+        self.permissions = OrderedSet::new();
+        self.public = false;
+    }
+
+}
+
+//The highest level address holds on path: the record's own grants and, until a record that doesn't inherit,
+//the grants of every folder above it. Ancestor owners don't count, only what was granted
+pub fn permission_level<'a, S: Storage>(store: &'a S, path: &String, namespace: &String, file: &File, address: &String, now: &BlockTime) -> Option<PermType> {
+    let groups = groups_of(store, address);
+    let mut level = file.level(address, &groups, now);
+
+    for (_, parent) in inherited_from(store, path, namespace, file) {
+        if level == Some(PermType::MANAGE) {
+            break;
+        }
+        level = cmp::max(level, parent.listed_level(address, &groups, now));
+    }
+    level
+}
+
+//The folders above path whose grants apply to it, nearest first
+fn inherited_from<'a, S: Storage>(store: &'a S, path: &String, namespace: &String, file: &File) -> Vec<(String, File)> {
+    let mut ancestors: Vec<(String, File)> = Vec::new();
+
+    let mut inherit = file.inherit;
+    let mut current = path.to_string();
+    while inherit {
+        current = parent_path(current);
+        if current.is_empty() {
+            break;
+        }
+        let parent = match bucket_load_readonly_file(store, &current, namespace) {
+            Ok(f) => f,
+            Err(_) => break,
+        };
+        inherit = parent.inherit;
+        ancestors.push((current.to_string(), parent));
+    }
+    ancestors
+}
+
+pub fn has_permission<'a, S: Storage>(store: &'a S, path: &String, namespace: &String, file: &File, address: String, level: PermType, now: &BlockTime) -> bool {
+    if level == PermType::READ && file.public {
+        return true;
+    }
+    match permission_level(store, path, namespace, file, &address, now) {
+        Some(held) => held >= level,
+        None => false,
+    }
+}
+
+pub fn can_read_inherited<'a, S: Storage>(store: &'a S, path: &String, namespace: &String, file: &File, address: String, now: &BlockTime) -> bool {
+    has_permission(store, path, namespace, file, address, PermType::READ, now)
+}
+
+pub fn can_write_inherited<'a, S: Storage>(store: &'a S, path: &String, namespace: &String, file: &File, address: String, now: &BlockTime) -> bool {
+    has_permission(store, path, namespace, file, address, PermType::WRITE, now)
+}
+
+pub fn try_set_inheritance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+    inherit: bool,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &namespace)?;
+
+    if !has_permission(&deps.storage, &path, &namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to change whether this file inherits permissions")).into());
+    }
+
+    f.inherit = inherit;
+    bucket_save_file(&mut deps.storage, &path, &f, &namespace, &now)?;
+    Ok(HandleResponse::default())
 }
 
 pub fn try_move_file<S: Storage, A: Api, Q: Querier>(
@@ -562,60 +1341,167 @@ pub fn try_move_file<S: Storage, A: Api, Q: Querier>(
     env: &Env,
     old_path: String,
     new_path: String,
+    drop_acl: bool,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    //folders carry their whole subtree with them
+    if old_path.ends_with('/') {
+        return try_move_folder(deps, env, old_path, new_path, drop_acl);
+    }
+
     debug_print!(
         "Attempting to move file from `{}` to `{}`",
         old_path,
         new_path
     );
 
-    let namespace = get_namespace_from_path(&deps, &old_path).unwrap_or(String::from("namespace not found!"));
+    if new_path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("File move unsuccessful. A file can't be moved to a folder path. new_path needs to end with the file name")).into());
+    }
+
+    let old_namespace = get_namespace_from_path(&deps, &old_path).unwrap_or(String::from("namespace not found!"));
+    let new_namespace = get_namespace_from_path(&deps, &new_path).unwrap_or(String::from("namespace not found!"));
 
     //only the owner of a file should be able to move it
     //if we only need to read from a file, we should utilize bucket_read because it's more gas efficient than bucket_load
-    let file = bucket_load_readonly_file(&mut deps.storage, &old_path, &namespace);
-    let file_res = match file {
+    let file = bucket_load_readonly_file(&mut deps.storage, &old_path, &old_namespace);
+    let mut file_res = match file {
         Ok(f) => f,
-        Err(_) => return Err(StdError::NotFound { kind: String::from("File move unsuccessful. This file does not exist. Check path is correct"), backtrace: None })
+        Err(_) => return Err(ContractError::NotFound(String::from("File move unsuccessful. This file does not exist. Check path is correct")).into())
     };
 
-    if env.message.sender.to_string() != file_res.owner {
-        return Err(StdError::GenericErr { msg: "You do not own this file and cannot move it".to_string(), backtrace: None })
+    if !has_permission(&deps.storage, &old_path, &old_namespace, &file_res, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("You are not permitted to move this file")).into())
     }
 
-    let duplicated_contents = file_res.contents;
+    let new_parent = parent_path(new_path.to_string());
+    let parent = match bucket_load_readonly_file(&deps.storage, &new_parent, &new_namespace) {
+        Ok(f) if f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("File move unsuccessful. Destination folder does not exist")).into())
+    };
+    if !can_write_inherited(&deps.storage, &new_parent, &new_namespace, &parent, sender_address(&deps.api, &env)?, &now) {
+        return Err(ContractError::Unauthorized(String::from("File move unsuccessful. Not permitted to write to destination folder")).into());
+    }
+
+    if bucket_load_readonly_file(&deps.storage, &new_path, &new_namespace).is_ok() {
+        return Err(ContractError::AlreadyExists(format!("File move unsuccessful. '{}' already exists", new_path)).into());
+    }
+
+    //the whole record moves, so owner, permissions and public flag stay as they were unless the mover asks to drop them
+    if drop_acl {
+        file_res.drop_acl();
+    }
 
-    //this was previously try_create_file
-    let new_file = try_create_file(
-        deps,
-        env,
-        &duplicated_contents,
-        new_path,
+    bucket_save_file(&mut deps.storage, &new_path, &file_res, &new_namespace, &now)?;
+    bucket_add_child(&mut deps.storage, &new_parent, &new_path, &new_namespace)?;
+
+    let versions = bucket_load_versions(&deps.storage, &old_path, &old_namespace)?;
+    bucket_save_versions(&mut deps.storage, &new_path, &versions, &new_namespace)?;
+
+    remove_entry(&mut deps.storage, &old_path, &old_namespace)?;
+    bucket_remove_child(&mut deps.storage, &parent_path(old_path.to_string()), &old_path, &old_namespace)?;
+
+    Ok(HandleResponse::default())
+}
+
+pub fn try_move_folder<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    old_path: String,
+    new_path: String,
+    drop_acl: bool,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    debug_print!(
+        "Attempting to move folder from `{}` to `{}`",
+        old_path,
+        new_path
     );
 
-    match new_file {
-        Ok(handle_response) => handle_response,
-        Err(e) => match e {
-            StdError::GenericErr { msg:_, backtrace:None } =>
-            return Err(StdError::GenericErr { msg: "File move unsuccessful. Not permitted to write to destination folder".to_string(), backtrace: None }),
+    if !new_path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("Folder move unsuccessful. A folder can only be moved to a path ending with '/'")).into());
+    }
+    if new_path.starts_with(&old_path) {
+        return Err(ContractError::InvalidRequest(String::from("Folder move unsuccessful. A folder can't be moved inside of itself")).into());
+    }
+
+    let old_parent = parent_path(old_path.to_string());
+    let new_parent = parent_path(new_path.to_string());
+    if old_parent.is_empty() || new_parent.is_empty() {
+        return Err(ContractError::InvalidRequest(String::from("Folder move unsuccessful. Root folders can't be moved or replaced")).into());
+    }
 
-            StdError::NotFound { kind:_, backtrace:None } =>
-            return Err(StdError::NotFound { kind: "File move unsuccessful. Destination folder does not exist".to_string(), backtrace: None }),
-            _ =>
-            return Err(StdError::GenericErr { msg: "It's impossible to reach this default branch".to_string(), backtrace: None }),
-        }
+    let old_namespace = get_namespace_from_path(&deps, &old_path).unwrap_or(String::from("namespace not found!"));
+    let new_namespace = get_namespace_from_path(&deps, &new_path).unwrap_or(String::from("namespace not found!"));
+
+    let folder = match bucket_load_readonly_file(&deps.storage, &old_path, &old_namespace) {
+        Ok(f) => f,
+        Err(_) => return Err(ContractError::NotFound(String::from("Folder move unsuccessful. This folder does not exist. Check path is correct")).into())
     };
 
-    let removed = try_remove_file(deps, env, old_path);
-    //if we were able to get contents of old_path above, then try_remove_file should always succeed, but I want to keep this here just incase
-    match removed {
-        Ok(handle_response) => handle_response,
-        Err(_e) => return Err(StdError::GenericErr {
-            msg: "Unable to remove file in old path".to_string(), backtrace: None })
+    //like a remove, a move takes MANAGE on every entry, otherwise MANAGE on a folder would let someone carry off files inside it they only hold less on
+    if !has_permission(&deps.storage, &old_path, &old_namespace, &folder, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("You are not permitted to move this folder")).into())
+    }
+    let subtree = collect_subtree(&deps.storage, &old_path, &old_namespace)?;
+    for entry in subtree.iter() {
+        let f = bucket_load_readonly_file(&deps.storage, entry, &old_namespace)?;
+        if !has_permission(&deps.storage, entry, &old_namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+            return Err(ContractError::Unauthorized(format!("You are not permitted to move '{}'", entry)).into());
+        }
+    }
+
+    let parent = match bucket_load_readonly_file(&deps.storage, &new_parent, &new_namespace) {
+        Ok(f) if f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("Folder move unsuccessful. Destination folder does not exist")).into())
     };
+    if !can_write_inherited(&deps.storage, &new_parent, &new_namespace, &parent, sender_address(&deps.api, &env)?, &now) {
+        return Err(ContractError::Unauthorized(String::from("Folder move unsuccessful. Not permitted to write to destination folder")).into());
+    }
+
+    //check the whole destination subtree before touching anything, so a failed move leaves both folders as they were
+    for entry in subtree.iter() {
+        let new_entry = rebase_path(entry, &old_path, &new_path);
+        if bucket_load_readonly_file(&deps.storage, &new_entry, &new_namespace).is_ok() {
+            return Err(ContractError::AlreadyExists(format!("Folder move unsuccessful. '{}' already exists", new_entry)).into());
+        }
+    }
+
+    for entry in subtree.iter() {
+        let new_entry = rebase_path(entry, &old_path, &new_path);
+
+        let mut f = bucket_load_readonly_file(&deps.storage, entry, &old_namespace)?;
+        sync_shared_index(&mut deps.storage, entry, &f.grantees(), &vec![])?;
+        if drop_acl {
+            f.drop_acl();
+        }
+        bucket_save_file(&mut deps.storage, &new_entry, &f, &new_namespace, &now)?;
+        bucket_remove_file(&mut deps.storage, entry, &old_namespace);
+
+        if entry.ends_with('/') {
+            let children = bucket_load_children(&deps.storage, entry, &old_namespace)?;
+            let mut new_children: OrderedSet<String> = OrderedSet::new();
+            for child in children.to_vec() {
+                new_children.push(rebase_path(child, &old_path, &new_path));
+            }
+            bucket_save_children(&mut deps.storage, &new_entry, &new_children, &new_namespace)?;
+            bucket_save_children(&mut deps.storage, entry, &OrderedSet::new(), &old_namespace)?;
+        } else {
+            let versions = bucket_load_versions(&deps.storage, entry, &old_namespace)?;
+            bucket_save_versions(&mut deps.storage, &new_entry, &versions, &new_namespace)?;
+            bucket_save_versions(&mut deps.storage, entry, &Vec::new(), &old_namespace)?;
+        }
+    }
+
+    bucket_remove_child(&mut deps.storage, &old_parent, &old_path, &old_namespace)?;
+    bucket_add_child(&mut deps.storage, &new_parent, &new_path, &new_namespace)?;
 
     Ok(HandleResponse::default())
+}
 
+//Swaps the old_root prefix of path for new_root. path must be inside of old_root
+fn rebase_path(path: &String, old_root: &String, new_root: &String) -> String {
+    format!("{}{}", new_root, &path[old_root.len()..])
 }
 
 pub fn try_move_multi_files<S: Storage, A: Api, Q: Querier>(
@@ -623,9 +1509,14 @@ pub fn try_move_multi_files<S: Storage, A: Api, Q: Querier>(
     env: Env,
     old_path_list: Vec<String>,
     new_path_list: Vec<String>,
+    drop_acl: bool,
 ) -> StdResult<HandleResponse> {
     debug_print!("Attempting to move multiple files");
 
+    if old_path_list.len() != new_path_list.len() {
+        return Err(ContractError::InvalidRequest(String::from("Move unsuccessful. old_path_list and new_path_list must be the same length")).into());
+    }
+
     for i in 0..old_path_list.len() {
         let old_path = &old_path_list[i];
         let new_path = &new_path_list[i];
@@ -635,6 +1526,7 @@ pub fn try_move_multi_files<S: Storage, A: Api, Q: Querier>(
             &env,
             old_path.to_string(),
             new_path.to_string(),
+            drop_acl,
         )?;
     }
 
@@ -644,11 +1536,129 @@ pub fn try_move_multi_files<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse::default())
 }
 
+pub fn try_copy_file<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    src: String,
+    dst: String,
+    recursive: bool,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    debug_print!(
+        "Attempting to copy `{}` to `{}`",
+        src,
+        dst
+    );
+
+    if src.ends_with('/') != dst.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("Copy unsuccessful. Files can only be copied to file paths and folders to folder paths")).into());
+    }
+    if src.ends_with('/') && !recursive {
+        return Err(ContractError::InvalidRequest(String::from("Copy unsuccessful. Set recursive to copy a folder")).into());
+    }
+    if dst == src {
+        return Err(ContractError::InvalidRequest(String::from("Copy unsuccessful. src and dst are the same path")).into());
+    }
+    if src.ends_with('/') && dst.starts_with(&src) {
+        return Err(ContractError::InvalidRequest(String::from("Copy unsuccessful. A folder can't be copied inside of itself")).into());
+    }
+
+    let sender = sender_address(&deps.api, &env)?;
+    let src_namespace = get_namespace_from_path(&deps, &src).unwrap_or(String::from("namespace not found!"));
+    let dst_namespace = get_namespace_from_path(&deps, &dst).unwrap_or(String::from("namespace not found!"));
+
+    let source = match bucket_load_readonly_file(&deps.storage, &src, &src_namespace) {
+        Ok(f) => f,
+        Err(_) => return Err(ContractError::NotFound(String::from("Copy unsuccessful. Source does not exist. Check path is correct")).into())
+    };
+    if !can_read_inherited(&deps.storage, &src, &src_namespace, &source, sender.to_string(), &now) {
+        return Err(ContractError::Unauthorized(String::from("Copy unsuccessful. Not permitted to read the source")).into());
+    }
+
+    let dst_parent = parent_path(dst.to_string());
+    let parent = match bucket_load_readonly_file(&deps.storage, &dst_parent, &dst_namespace) {
+        Ok(f) if f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("Copy unsuccessful. Destination folder does not exist")).into())
+    };
+    if !can_write_inherited(&deps.storage, &dst_parent, &dst_namespace, &parent, sender.to_string(), &now) {
+        return Err(ContractError::Unauthorized(String::from("Copy unsuccessful. Not permitted to write to destination folder")).into());
+    }
+
+    //only what the sender can read gets copied. An unreadable folder is left out together with everything below it
+    let mut entries: Vec<String> = vec![src.to_string()];
+    let mut i = 0;
+    while i < entries.len() {
+        if entries[i].ends_with('/') {
+            for child in bucket_load_children(&deps.storage, &entries[i], &src_namespace)?.to_vec() {
+                let readable = match bucket_load_readonly_file(&deps.storage, child, &src_namespace) {
+                    Ok(f) => can_read_inherited(&deps.storage, child, &src_namespace, &f, sender.to_string(), &now),
+                    Err(_) => false,
+                };
+                if readable {
+                    entries.push(child.to_string());
+                }
+            }
+        }
+        i += 1;
+    }
+
+    //check every destination before writing, so a failed copy leaves the destination as it was
+    for entry in entries.iter() {
+        let new_entry = rebase_path(entry, &src, &dst);
+        if bucket_load_readonly_file(&deps.storage, &new_entry, &dst_namespace).is_ok() {
+            return Err(ContractError::AlreadyExists(format!("Copy unsuccessful. '{}' already exists", new_entry)).into());
+        }
+    }
+
+    //a copy is a new record: the sender owns it and nothing is shared yet
+    for entry in entries.iter() {
+        let new_entry = rebase_path(entry, &src, &dst);
+        let f = bucket_load_readonly_file(&deps.storage, entry, &src_namespace)?;
+        let copy = if f.is_folder() {
+            File::new_folder(&sender, f.get_contents(), env)
+        } else {
+            File::new(&sender, f.get_contents(), env).with_content_type(f.metadata.content_type.clone())
+        };
+
+        bucket_save_file(&mut deps.storage, &new_entry, &copy, &dst_namespace, &now)?;
+        bucket_add_child(&mut deps.storage, &parent_path(new_entry.to_string()), &new_entry, &dst_namespace)?;
+    }
+
+    Ok(HandleResponse::default())
+}
+
+pub fn try_copy_multi_files<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    src_list: Vec<String>,
+    dst_list: Vec<String>,
+    recursive: bool,
+) -> StdResult<HandleResponse> {
+    debug_print!("Attempting to copy multiple files");
+
+    if src_list.len() != dst_list.len() {
+        return Err(ContractError::InvalidRequest(String::from("Copy unsuccessful. src_list and dst_list must be the same length")).into());
+    }
+
+    for i in 0..src_list.len() {
+        let _res = try_copy_file(
+            deps,
+            &env,
+            src_list[i].to_string(),
+            dst_list[i].to_string(),
+            recursive,
+        )?;
+    }
+
+    Ok(HandleResponse::default())
+}
+
 pub fn try_remove_file<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
     path: String,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
 
     let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
     //I think getting namespace from path is needed because we could have a situation in which
@@ -663,75 +1673,303 @@ pub fn try_remove_file<S: Storage, A: Api, Q: Querier>(
     let res = bucket_load_readonly_file(&deps.storage, &path, &namespace);
     match res {
         Ok(f) => {
-            if f.owner == env.message.sender.to_string() {
-                bucket_remove_file(&mut deps.storage, &path, &namespace);
+            if has_permission(&deps.storage, &path, &namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+                //removing a folder that still has children would leave them orphaned
+                if f.is_folder() && bucket_load_children(&deps.storage, &path, &namespace)?.len() > 0 {
+                    return Err(ContractError::InvalidRequest(String::from("This folder is not empty. Use RemoveFolder to remove it along with its contents")).into());
+                }
+
+                remove_entry(&mut deps.storage, &path, &namespace)?;
+
+                let par_path = parent_path(path.to_string());
+                if !par_path.is_empty() {
+                    bucket_remove_child(&mut deps.storage, &par_path, &path, &namespace)?;
+                }
                 return Ok(HandleResponse::default());
             }
-            Err(StdError::GenericErr { msg: "Sorry. You are not authorized to remove this file".to_string(), backtrace: None })
+            Err(ContractError::Unauthorized(String::from("Unauthorized to remove this file")).into())
         }
         Err(_e) => {
-            Err(StdError::NotFound { kind: "This path does not exist. Cannot remove.".to_string(), backtrace: None })
+            Err(ContractError::NotFound(String::from("This path does not exist. Cannot remove.")).into())
+        }
+    }
+}
+
+pub fn try_remove_folder<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+    recursive: bool,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    debug_print!("Attempting to remove folder `{}`", path);
+
+    if !path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("This path is not a folder. Use Remove to remove a file")).into());
+    }
+
+    let par_path = parent_path(path.to_string());
+    if par_path.is_empty() {
+        return Err(ContractError::InvalidRequest(String::from("Root folders can't be removed. Use ForgetMe instead")).into());
+    }
+
+    let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
+
+    if bucket_load_readonly_file(&deps.storage, &path, &namespace).is_err() {
+        return Err(ContractError::NotFound(String::from("This path does not exist. Cannot remove.")).into());
+    }
+
+    if !recursive && bucket_load_children(&deps.storage, &path, &namespace)?.len() > 0 {
+        return Err(ContractError::InvalidRequest(String::from("This folder is not empty. Set recursive to remove it along with its contents")).into());
+    }
+
+    //the sender needs MANAGE on every entry. Check them all first so that nothing is removed if one of them is missing it
+    let subtree = collect_subtree(&deps.storage, &path, &namespace)?;
+    for entry in subtree.iter() {
+        let f = bucket_load_readonly_file(&deps.storage, entry, &namespace)?;
+        if !has_permission(&deps.storage, entry, &namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+            return Err(ContractError::Unauthorized(format!("Unauthorized to remove '{}'", entry)).into());
         }
     }
+
+    for entry in subtree.iter() {
+        remove_entry(&mut deps.storage, entry, &namespace)?;
+    }
+    bucket_remove_child(&mut deps.storage, &par_path, &path, &namespace)?;
+
+    Ok(HandleResponse::default())
 }
 
-fn parent_path(mut path: String) -> String {
+//Removes the file at path along with anything stored for it on the side. Does not touch the parent's child index
+fn remove_entry<'a, S: Storage>(store: &'a mut S, path: &String, namespace: &String) -> StdResult<()> {
+    if let Ok(f) = bucket_load_readonly_file(store, path, namespace) {
+        sync_shared_index(store, path, &f.grantees(), &Vec::new())?;
+    }
+    bucket_remove_file(store, path, namespace);
     if path.ends_with('/') {
-        path.pop();
+        bucket_save_children(store, path, &OrderedSet::new(), namespace)?;
+    } else {
+        bucket_save_versions(store, path, &Vec::new(), namespace)?;
     }
-    let split = path.split('/');
-    let vec = split.collect::<Vec<&str>>();
+    Ok(())
+}
 
-    let mut par_path = String::new();
+fn parent_path(path: String) -> String {
+    //root folders and malformed paths have no parent
+    match Path::parse(&path) {
+        Ok(p) => p.parent().map(|par| par.to_string()).unwrap_or_default(),
+        Err(_) => String::new(),
+    }
+}
 
-    let mut i = 0;
-    while i < vec.len() - 1 {
-        let s = vec[i];
-        par_path.push_str(s);
-        par_path.push('/');
-        i += 1;
+pub fn try_create_file<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    contents: &String,
+    path: String,
+    content_type: Option<String>,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    if path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("File creation unsuccessful. Create only makes files. Use CreateFolder to make a folder")).into());
+    }
+
+    let ha = deps
+        .api
+        .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
+        
+    let par_path = parent_path(path.to_string());
+
+    let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
+    let res = bucket_load_readonly_file(&deps.storage, &par_path, &namespace);
+
+    match res {
+        Ok(f) => {
+            if !f.is_folder() {
+                return Err(ContractError::InvalidRequest(format!("File creation unsuccessful. Parent path: '{}' is not a folder.", &par_path)).into());
+            }
+
+            //writing over an existing file only needs permission on the file itself, and keeps what was there as a version
+            if let Ok(existing) = bucket_load_readonly_file(&deps.storage, &path, &namespace) {
+                if !can_write_inherited(&deps.storage, &path, &namespace, &existing, ha.to_string(), &now) {
+                    return Err(ContractError::Unauthorized(String::from("Unauthorized to write to this file.")).into());
+                }
+                write_version(deps, env, &path, &namespace, existing, &contents, content_type)?;
+                return Ok(HandleResponse::default());
+            }
+
+            if can_write_inherited(&deps.storage, &par_path, &namespace, &f, ha.to_string(), &now) {
+                // Add new file to bucket
+                create_file(
+                    deps,
+                    &path,
+                    File::new(ha.as_str(), &contents, env).with_content_type(content_type),
+                    &now,
+                )?;
+
+                //All code for write_claim() removed
+
+                return Ok(HandleResponse::default());
+            }
+            Err(ContractError::Unauthorized(String::from("Unauthorized to create a file in this folder.")).into())
+        }
+        Err(_e) => {
+            Err(ContractError::NotFound(format!("File creation unsuccessful. Parent path: '{}' doesn't exist.", &par_path)).into())
+        }
+    }
+}
+
+//Archives the current contents of file as a version and writes contents over them.
+//Only as many versions as the file's retention allows are kept, the oldest go first
+fn write_version<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: &String,
+    namespace: &String,
+    mut file: File,
+    contents: &str,
+    content_type: Option<String>,
+) -> StdResult<()> {
+    let now = BlockTime::from_env(&env);
+    let retention = version_retention(&deps.storage, &deps.api, &file)?;
+
+    let mut versions = bucket_load_versions(&deps.storage, path, namespace)?;
+    versions.push(FileVersion { contents: file.contents.clone(), metadata: file.metadata.clone() });
+    while versions.len() > retention {
+        versions.remove(0);
+    }
+    bucket_save_versions(&mut deps.storage, path, &versions, namespace)?;
+
+    file.update_contents(&sender_address(&deps.api, env)?, contents, content_type, env);
+    bucket_save_file(&mut deps.storage, path, &file, namespace, &now)?;
+    Ok(())
+}
+
+fn version_retention<'a, S: Storage, A: Api>(store: &'a S, api: &A, file: &File) -> StdResult<usize> {
+    let retention = match file.retention {
+        Some(r) => r,
+        None => {
+            let wallet = bucket_load_wallet(store, api, &file.owner)?;
+            wallet.and_then(|w| w.version_retention).unwrap_or(DEFAULT_VERSION_RETENTION)
+        }
+    };
+    Ok(cmp::min(retention, MAX_VERSION_RETENTION) as usize)
+}
+
+pub fn try_revert_file<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: String,
+    version: u32,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    debug_print!("Attempting to revert `{}` to version {}", path, version);
+
+    let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
+
+    let file = match bucket_load_readonly_file(&deps.storage, &path, &namespace) {
+        Ok(f) if !f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("Revert unsuccessful. This file does not exist. Check path is correct")).into()),
+    };
+    if !can_write_inherited(&deps.storage, &path, &namespace, &file, sender_address(&deps.api, &env)?, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to write to this file.")).into());
+    }
+
+    //reverting is a write of its own, so the contents being replaced are kept as a version too
+    let versions = bucket_load_versions(&deps.storage, &path, &namespace)?;
+    let old = match versions.into_iter().find(|v| v.metadata.version == version) {
+        Some(v) => v,
+        None => return Err(ContractError::NotFound(format!("Revert unsuccessful. Version {} of this file is not kept", version)).into()),
+    };
+
+    write_version(deps, env, &path, &namespace, file, &old.contents, old.metadata.content_type)?;
+
+    Ok(HandleResponse::default())
+}
+
+//Sets how many prior versions are kept, for a single file when path is given or for every file the sender owns otherwise.
+//None goes back to the default
+pub fn try_set_retention<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    path: Option<String>,
+    versions: Option<u32>,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    if let Some(v) = versions {
+        if v > MAX_VERSION_RETENTION {
+            return Err(ContractError::QuotaExceeded(format!("Retention can't be more than {} versions", MAX_VERSION_RETENTION)).into());
+        }
+    }
+
+    let sender = sender_address(&deps.api, &env)?;
+
+    match path {
+        Some(path) => {
+            let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
+            let mut file = match bucket_load_readonly_file(&deps.storage, &path, &namespace) {
+                Ok(f) if !f.is_folder() => f,
+                _ => return Err(ContractError::NotFound(String::from("This file does not exist. Check path is correct")).into()),
+            };
+            if !has_permission(&deps.storage, &path, &namespace, &file, sender.to_string(), PermType::MANAGE, &now) {
+                return Err(ContractError::Unauthorized(String::from("Unauthorized to change how many versions of this file are kept")).into());
+            }
+
+            file.retention = versions;
+            bucket_save_file(&mut deps.storage, &path, &file, &namespace, &now)?;
+
+            //drop whatever no longer fits right away instead of waiting for the next write
+            let retention = version_retention(&deps.storage, &deps.api, &file)?;
+            let mut kept = bucket_load_versions(&deps.storage, &path, &namespace)?;
+            if kept.len() > retention {
+                kept.drain(..kept.len() - retention);
+                bucket_save_versions(&mut deps.storage, &path, &kept, &namespace)?;
+            }
+        }
+        None => {
+            let mut wallet = load_wallet(&deps.storage, &deps.api, &sender)?;
+            wallet.version_retention = versions;
+            bucket_save_wallet(&mut deps.storage, &deps.api, &sender, &wallet)?;
+        }
     }
 
-    par_path
+    Ok(HandleResponse::default())
 }
 
-pub fn try_create_file<S: Storage, A: Api, Q: Querier>(
+pub fn try_create_folder<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
     contents: &String,
     path: String,
-
 ) -> StdResult<HandleResponse> {
-    let ha = deps
-        .api
-        .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
-        
+    let now = BlockTime::from_env(&env);
+    if !path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(String::from("Folder creation unsuccessful. Folder paths end with '/'")).into());
+    }
+
     let par_path = parent_path(path.to_string());
+    if par_path.is_empty() {
+        return Err(ContractError::InvalidRequest(String::from("Folder creation unsuccessful. Root folders are made by InitAddress")).into());
+    }
 
     let namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace does not exist!"));
-    let res = bucket_load_readonly_file(&deps.storage, &par_path, &namespace);
+    let parent = match bucket_load_readonly_file(&deps.storage, &par_path, &namespace) {
+        Ok(f) if f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(format!("Folder creation unsuccessful. Parent folder: '{}' doesn't exist.", &par_path)).into()),
+    };
+    if !can_write_inherited(&deps.storage, &par_path, &namespace, &parent, sender_address(&deps.api, &env)?, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to create a folder in this folder.")).into());
+    }
 
-    match res {
-        Ok(f) => {
-            if f.can_write(ha.to_string()) {
-                // Add new file to bucket
-                create_file(
-                    deps,
-                    ha.to_string(),
-                    &path,
-                    &contents,
-                );
+    //replacing a folder's record would leave its children behind under a new owner
+    if bucket_load_readonly_file(&deps.storage, &path, &namespace).is_ok() {
+        return Err(ContractError::AlreadyExists(format!("Folder creation unsuccessful. '{}' already exists", path)).into());
+    }
 
-                //All code for write_claim() removed
+    let owner = sender_address(&deps.api, env)?;
+    create_file(deps, &path, File::new_folder(&owner, contents, env), &now)?;
 
-                return Ok(HandleResponse::default());
-            }
-            Err(StdError::GenericErr { msg: "Sorry. You are unauthorized to create a file in this folder.".to_string(), backtrace: None })
-        }
-        Err(_e) => {
-            Err(StdError::NotFound { kind: format!("File creation unsuccessful. Parent path: '{}' doesn't exist.", &par_path), backtrace: None })
-        }
-    }
+    Ok(HandleResponse::default())
 }
 
 pub fn try_create_multi_files<S: Storage, A: Api, Q: Querier>(
@@ -746,6 +1984,10 @@ pub fn try_create_multi_files<S: Storage, A: Api, Q: Querier>(
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
     debug_print!("Attempting to create multiple files for account: {}", ha);
 
+    if contents_list.len() != paths.len() {
+        return Err(ContractError::InvalidRequest(String::from("File creation unsuccessful. contents_list and path_list must be the same length")).into());
+    }
+
     for i in 0..contents_list.len() {
         let file_contents = &contents_list[i];
         let path = paths[i].to_string();
@@ -755,6 +1997,7 @@ pub fn try_create_multi_files<S: Storage, A: Api, Q: Querier>(
             &env,
             file_contents,
             path,
+            None,
         )?;
     }
 
@@ -781,12 +2024,10 @@ pub fn try_remove_multi_files<S: Storage, A: Api, Q: Querier>(
 
 pub fn create_file<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>, //used to just be <'a, S: Storage>(store: &'a mut S),
-    owner: String,
     path: &String,
-    contents: &String,
-) {
-    let file = File::new(&owner, contents);
-
+    file: File,
+    now: &BlockTime,
+) -> StdResult<()> {
     //below allows user to create a file in anyone else's folder, if they had write permissions.
     //They can also move a file that they owned into anyone else's folder, if they had write permissions.
     //The file they owned could be given to them by anyone
@@ -794,37 +2035,76 @@ pub fn create_file<S: Storage, A: Api, Q: Querier>(
     //and move files within and to their own root directory
 
     let namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace does not exist!"));
-    bucket_save_file(&mut deps.storage, &path, &file, &namespace);
+    bucket_save_file(&mut deps.storage, &path, &file, &namespace, now)?;
+
+    //keep the parent's child index up to date so folders can be listed. The root folder has no parent
+    let par_path = parent_path(path.to_string());
+    if !par_path.is_empty() {
+        bucket_add_child(&mut deps.storage, &par_path, path, &namespace)?;
+    }
+    Ok(())
 }
 
-pub fn bucket_save_file<'a, S: Storage>(store: &'a mut S, path: &String, folder: &File, namespace: &String) {
+pub fn bucket_save_file<'a, S: Storage>(store: &'a mut S, path: &String, folder: &File, namespace: &String, now: &BlockTime) -> StdResult<()> {
+    //expired grants are cleaned up whenever a record gets written
+    let mut folder = folder.clone();
+    folder.drop_expired(now);
 
-    let bucket_response = bucket(namespace.as_bytes(), store).save(path.as_bytes(), folder);
-    
-    match bucket_response {
-        Ok(bucket_response) => bucket_response,
-        Err(e) => panic!("Bucket Save Error: {}", e),
+    //keep the shared-with-me index in step with whoever was added to or dropped from the permissions
+    //a legacy record being written over never had its grants indexed
+    let before = match load_stored_file(store, path, namespace)? {
+        Some(StoredFile::Current(previous)) => previous.grantees(),
+        _ => Vec::new(),
+    };
+    sync_shared_index(store, path, &before, &folder.grantees())?;
+
+    bucket(namespace.as_bytes(), store).save(path.as_bytes(), &folder)
+}
+
+//Adds path to the index of every grantee in after but not before, and takes it out for the reverse
+fn sync_shared_index<'a, S: Storage>(store: &'a mut S, path: &String, before: &Vec<String>, after: &Vec<String>) -> StdResult<()> {
+    for grantee in before.iter().filter(|g| !after.contains(g)) {
+        let mut shared = bucket_load_shared(store, grantee)?;
+        shared.remove(path.to_string());
+        bucket_save_shared(store, grantee, &shared)?;
+    }
+    for grantee in after.iter().filter(|g| !before.contains(g)) {
+        let mut shared = bucket_load_shared(store, grantee)?;
+        shared.push(path.to_string());
+        bucket_save_shared(store, grantee, &shared)?;
+    }
+    Ok(())
+}
+
+//The paths grantee, an address or a group, has been granted something on
+pub fn bucket_load_shared<'a, S: Storage>(store: &'a S, grantee: &String) -> StdResult<OrderedSet<String>> {
+    let shared: Option<OrderedSet<String>> = bucket_read(SHARED_WITH_LOCATION, store).may_load(grantee.as_bytes())?;
+    Ok(shared.unwrap_or_else(OrderedSet::new))
+}
+
+fn bucket_save_shared<'a, S: Storage>(store: &'a mut S, grantee: &String, shared: &OrderedSet<String>) -> StdResult<()> {
+    let mut shared_bucket = bucket(SHARED_WITH_LOCATION, store);
+    if shared.len() == 0 {
+        shared_bucket.remove(grantee.as_bytes());
+        return Ok(());
     }
+    shared_bucket.save(grantee.as_bytes(), shared)
 }
 
 pub fn bucket_remove_file<'a, S: Storage>(store: &'a mut S, path: &String, namespace: &String) {
     bucket::<S, File>(namespace.as_bytes(), store).remove(path.as_bytes());
 }
 //need to make file_exists use bucket read
+//Legacy records count too, so a wallet that hasn't been migrated yet can't be initialized over
 pub fn file_exists<'a, S: Storage>(store: &'a mut S, path: &String, namespace: &String) -> bool {
-    let f: Result<File, StdError> = bucket(namespace.as_bytes(), store).load(path.as_bytes());
-
-    match f {
-        Ok(_file) => true,
-        Err(_error) => false,
-    }
+    matches!(load_stored_file(store, path, namespace), Ok(Some(_)))
 }
 
 pub fn bucket_load_file<'a, S: Storage>(store: &'a mut S, path: &String, namespace: &String) -> StdResult<File> {
     let f: Result<File, StdError> = bucket(namespace.as_bytes(), store).load(path.as_bytes());
     match f {
         Ok(file) => Ok(file),
-        Err(_error) => Err(StdError::NotFound { kind: String::from("No file found at this path."), backtrace: None })
+        Err(_error) => Err(ContractError::NotFound(String::from("No file found at this path.")).into())
     }
 }
 
@@ -836,12 +2116,94 @@ pub fn bucket_load_readonly_file<'a, S: Storage>(
     bucket_read(namespace.as_bytes(), store).load(path.as_bytes())
 }
 
+//Child index: every folder keeps an OrderedSet of the full paths of its direct children.
+//Storage can't be iterated, so this is the only way to find out what lives inside of a folder
+pub fn bucket_load_children<'a, S: Storage>(
+    store: &'a S,
+    path: &String,
+    namespace: &String
+) -> StdResult<OrderedSet<String>> {
+    let children: Option<OrderedSet<String>> =
+        ReadonlyBucket::multilevel(&[FOLDER_CHILDREN_LOCATION, namespace.as_bytes()], store).may_load(path.as_bytes())?;
+    Ok(children.unwrap_or(OrderedSet::new()))
+}
+
+pub fn bucket_save_children<'a, S: Storage>(
+    store: &'a mut S,
+    path: &String,
+    children: &OrderedSet<String>,
+    namespace: &String
+) -> StdResult<()> {
+    let mut children_bucket = Bucket::multilevel(&[FOLDER_CHILDREN_LOCATION, namespace.as_bytes()], store);
+    if children.len() == 0 {
+        children_bucket.remove(path.as_bytes());
+        return Ok(());
+    }
+    children_bucket.save(path.as_bytes(), children)
+}
+
+pub fn bucket_add_child<'a, S: Storage>(store: &'a mut S, parent: &String, child: &String, namespace: &String) -> StdResult<()> {
+    let mut children = bucket_load_children(store, parent, namespace)?;
+    children.push(child.to_string());
+    bucket_save_children(store, parent, &children, namespace)
+}
+
+pub fn bucket_remove_child<'a, S: Storage>(store: &'a mut S, parent: &String, child: &String, namespace: &String) -> StdResult<()> {
+    let mut children = bucket_load_children(store, parent, namespace)?;
+    children.remove(child.to_string());
+    bucket_save_children(store, parent, &children, namespace)
+}
+
+//Prior versions of a file, oldest first, kept next to the file under the same path
+pub fn bucket_load_versions<'a, S: Storage>(
+    store: &'a S,
+    path: &String,
+    namespace: &String
+) -> StdResult<Vec<FileVersion>> {
+    let versions: Option<Vec<FileVersion>> =
+        ReadonlyBucket::multilevel(&[FILE_VERSIONS_LOCATION, namespace.as_bytes()], store).may_load(path.as_bytes())?;
+    Ok(versions.unwrap_or_default())
+}
+
+pub fn bucket_save_versions<'a, S: Storage>(
+    store: &'a mut S,
+    path: &String,
+    versions: &Vec<FileVersion>,
+    namespace: &String
+) -> StdResult<()> {
+    let mut versions_bucket = Bucket::multilevel(&[FILE_VERSIONS_LOCATION, namespace.as_bytes()], store);
+    if versions.is_empty() {
+        versions_bucket.remove(path.as_bytes());
+        return Ok(());
+    }
+    versions_bucket.save(path.as_bytes(), versions)
+}
+
+//Returns path and every path below it. Parents always come before their children
+pub fn collect_subtree<'a, S: Storage>(store: &'a S, path: &String, namespace: &String) -> StdResult<Vec<String>> {
+    let mut subtree: Vec<String> = vec![path.to_string()];
+
+    let mut i = 0;
+    while i < subtree.len() {
+        if subtree[i].ends_with('/') {
+            let children = bucket_load_children(store, &subtree[i], namespace)?;
+            for child in children.to_vec() {
+                subtree.push(child.to_string());
+            }
+        }
+        i += 1;
+    }
+
+    Ok(subtree)
+}
+
 // QueryMsg
 pub fn query_file<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     path: String,
     behalf: &HumanAddr,
 ) -> StdResult<FileResponse> {
+    let now = last_block(&deps.storage);
 
     let full_namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace not found!"));
 
@@ -849,16 +2211,326 @@ pub fn query_file<S: Storage, A: Api, Q: Querier>(
 
     match f {
         Ok(f1) => {
-            if f1.can_read(String::from(behalf.as_str())) {
+            if can_read_inherited(&deps.storage, &path, &full_namespace, &f1, String::from(behalf.as_str()), &now) {
                 return Ok(FileResponse { file: f1 });
             }
-            Err(StdError::GenericErr { msg: "Sorry bud! Unauthorized to read file.".to_string(), backtrace: None })
+            Err(ContractError::Unauthorized(String::from("Unauthorized to read file.")).into())
         }
 
         Err(_err) => {
-            Err(StdError::NotFound { kind: "File not found. Incorrect path or root directory.".to_string(), backtrace: None })
+            Err(ContractError::NotFound(String::from("File not found. Incorrect path or root directory.")).into())
+        }
+    }
+}
+
+//Needs no viewing key. Private files answer the same way missing ones do, so nothing is learned about them
+pub fn query_public_contents<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+) -> StdResult<PublicContentsResponse> {
+    let full_namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace not found!"));
+
+    match bucket_load_readonly_file(&deps.storage, &path, &full_namespace) {
+        Ok(f) if f.is_public() => Ok(PublicContentsResponse {
+            contents: f.contents,
+            content_type: f.metadata.content_type,
+        }),
+        _ => Err(ContractError::NotFound(String::from("No public file found at this path.")).into()),
+    }
+}
+
+pub fn query_stat<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+) -> StdResult<StatResponse> {
+    let now = last_block(&deps.storage);
+    let full_namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace not found!"));
+
+    let f = match bucket_load_readonly_file(&deps.storage, &path, &full_namespace) {
+        Ok(f) => f,
+        Err(_err) => return Err(ContractError::NotFound(String::from("File not found. Incorrect path or root directory.")).into()),
+    };
+
+    if !can_read_inherited(&deps.storage, &path, &full_namespace, &f, String::from(behalf.as_str()), &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to read file.")).into());
+    }
+
+    Ok(StatResponse { metadata: f.metadata })
+}
+
+//Loads a file that behalf can read, for the version queries
+fn load_readable_file<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: &String,
+    behalf: &HumanAddr,
+) -> StdResult<(File, String)> {
+    let now = last_block(&deps.storage);
+    let full_namespace = get_namespace_from_path(&deps, path).unwrap_or(String::from("namespace not found!"));
+
+    let f = match bucket_load_readonly_file(&deps.storage, path, &full_namespace) {
+        Ok(f) if !f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("File not found. Incorrect path or root directory.")).into()),
+    };
+
+    if !can_read_inherited(&deps.storage, path, &full_namespace, &f, String::from(behalf.as_str()), &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to read file.")).into());
+    }
+
+    Ok((f, full_namespace))
+}
+
+//Who has access to path and how, for anyone who can manage it. Expired grants are left out
+pub fn query_permissions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+) -> StdResult<PermissionsResponse> {
+    let now = last_block(&deps.storage);
+    let full_namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace not found!"));
+
+    let f = match bucket_load_readonly_file(&deps.storage, &path, &full_namespace) {
+        Ok(f) => f,
+        Err(_err) => return Err(ContractError::NotFound(String::from("File not found. Incorrect path or root directory.")).into()),
+    };
+
+    if !has_permission(&deps.storage, &path, &full_namespace, &f, String::from(behalf.as_str()), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to view the permissions of this file")).into());
+    }
+
+    let inherited = inherited_from(&deps.storage, &path, &full_namespace, &f)
+        .into_iter()
+        .map(|(folder, parent)| InheritedPermissions { path: folder, permissions: parent.live_permissions(&now) })
+        .filter(|i| !i.permissions.is_empty())
+        .collect();
+
+    Ok(PermissionsResponse {
+        owner: f.owner.to_string(),
+        public: f.public,
+        inherit: f.inherit,
+        pending_owner: f.pending_owner.clone(),
+        permissions: f.live_permissions(&now),
+        inherited,
+    })
+}
+
+//Whether address holds at least level on path. Anyone can ask about themselves, only managers can ask about
+//someone else. A path that doesn't exist gives false, the same as one address can't get into
+pub fn query_check_access<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+    address: String,
+    level: PermType,
+) -> StdResult<CheckAccessResponse> {
+    let now = last_block(&deps.storage);
+    let address = normalize(&deps.api, &address)?;
+    let full_namespace = get_namespace_from_path(&deps, &path).unwrap_or(String::from("namespace not found!"));
+
+    let f = match bucket_load_readonly_file(&deps.storage, &path, &full_namespace) {
+        Ok(f) => f,
+        Err(_err) => return Ok(CheckAccessResponse { allowed: false }),
+    };
+
+    if address != behalf.as_str() && !has_permission(&deps.storage, &path, &full_namespace, &f, String::from(behalf.as_str()), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to check the access of other addresses")).into());
+    }
+
+    let allowed = has_permission(&deps.storage, &path, &full_namespace, &f, address, level, &now);
+    Ok(CheckAccessResponse { allowed })
+}
+
+pub fn query_versions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+) -> StdResult<VersionsResponse> {
+    let (f, full_namespace) = load_readable_file(deps, &path, behalf)?;
+
+    let versions = bucket_load_versions(&deps.storage, &path, &full_namespace)?;
+
+    Ok(VersionsResponse {
+        current: f.metadata,
+        versions: versions.into_iter().map(|v| v.metadata).collect(),
+    })
+}
+
+pub fn query_version_contents<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+    version: u32,
+) -> StdResult<VersionContentsResponse> {
+    let (f, full_namespace) = load_readable_file(deps, &path, behalf)?;
+
+    if f.metadata.version == version {
+        return Ok(VersionContentsResponse { version: FileVersion { contents: f.contents, metadata: f.metadata } });
+    }
+
+    let versions = bucket_load_versions(&deps.storage, &path, &full_namespace)?;
+    match versions.into_iter().find(|v| v.metadata.version == version) {
+        Some(v) => Ok(VersionContentsResponse { version: v }),
+        None => Err(ContractError::NotFound(format!("Version {} of this file is not kept", version)).into()),
+    }
+}
+
+//Shared by the folder queries: makes sure path is a folder that behalf can read and returns its namespace
+fn load_readable_folder<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: &String,
+    behalf: &HumanAddr,
+) -> StdResult<String> {
+    let now = last_block(&deps.storage);
+    if !path.ends_with('/') {
+        return Err(ContractError::InvalidRequest(format!("'{}' is not a folder. Folder paths end with '/'", path)).into());
+    }
+
+    let full_namespace = get_namespace_from_path(&deps, path).unwrap_or(String::from("namespace not found!"));
+
+    let folder = match bucket_load_readonly_file(&deps.storage, path, &full_namespace) {
+        Ok(f) if f.is_folder() => f,
+        _ => return Err(ContractError::NotFound(String::from("Folder not found. Incorrect path or root directory.")).into()),
+    };
+
+    if !can_read_inherited(&deps.storage, path, &full_namespace, &folder, String::from(behalf.as_str()), &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to read folder.")).into());
+    }
+
+    Ok(full_namespace)
+}
+
+pub fn query_folder_contents<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+) -> StdResult<FolderContentsResponse> {
+    let now = last_block(&deps.storage);
+    let full_namespace = load_readable_folder(deps, &path, behalf)?;
+
+    let children = bucket_load_children(&deps.storage, &path, &full_namespace)?;
+
+    let mut folders: Vec<String> = Vec::new();
+    let mut files: Vec<String> = Vec::new();
+    for child in children.to_vec() {
+        //only list what behalf is actually allowed to read
+        if let Ok(f) = bucket_load_readonly_file(&deps.storage, child, &full_namespace) {
+            if can_read_inherited(&deps.storage, child, &full_namespace, &f, String::from(behalf.as_str()), &now) {
+                if f.is_folder() {
+                    folders.push(child.to_string());
+                } else {
+                    files.push(child.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(FolderContentsResponse { parent: path, folders, files })
+}
+
+//Walks the subtree under path one level at a time. Anything behalf can't read is left out, and so is everything below it.
+//max_entries caps how many children get looked at, not how many come back, so unreadable entries can't make the walk unbounded
+pub fn query_folder_tree<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    path: String,
+    behalf: &HumanAddr,
+    max_depth: Option<u32>,
+    max_entries: Option<u32>,
+) -> StdResult<BigTreeResponse> {
+    let now = last_block(&deps.storage);
+    let full_namespace = load_readable_folder(deps, &path, behalf)?;
+
+    let max_depth = cmp::min(max_depth.unwrap_or(DEFAULT_TREE_DEPTH), MAX_TREE_DEPTH);
+    let max_entries = cmp::min(max_entries.unwrap_or(DEFAULT_TREE_ENTRIES), MAX_TREE_ENTRIES) as usize;
+
+    let mut folders: Vec<String> = Vec::new();
+    let mut files: Vec<String> = Vec::new();
+
+    let mut visited: usize = 0;
+    let mut level: Vec<String> = vec![path];
+    let mut depth = 0;
+    'walk: while depth < max_depth && !level.is_empty() {
+        let mut next_level: Vec<String> = Vec::new();
+
+        for folder_path in level.iter() {
+            let children = bucket_load_children(&deps.storage, folder_path, &full_namespace)?;
+
+            for child in children.to_vec() {
+                if visited >= max_entries {
+                    break 'walk;
+                }
+                visited += 1;
+
+                if let Ok(f) = bucket_load_readonly_file(&deps.storage, child, &full_namespace) {
+                    if can_read_inherited(&deps.storage, child, &full_namespace, &f, String::from(behalf.as_str()), &now) {
+                        if f.is_folder() {
+                            folders.push(child.to_string());
+                            next_level.push(child.to_string());
+                        } else {
+                            files.push(child.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        level = next_level;
+        depth += 1;
+    }
+
+    Ok(BigTreeResponse { folders, files })
+}
+
+//Everything behalf has been granted something on, directly or through a group, sorted by path. Entries whose grants have
+//expired, or whose file has since been replaced, are left out
+pub fn query_shared_with_me<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    behalf: &HumanAddr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SharedWithMeResponse> {
+    let address = behalf.to_string();
+    let limit = cmp::min(limit.unwrap_or(DEFAULT_SHARED_LIMIT), MAX_SHARED_LIMIT) as usize;
+    let now = last_block(&deps.storage);
+    let groups = groups_of(&deps.storage, &address);
+
+    let mut paths: Vec<String> = bucket_load_shared(&deps.storage, &address)?.to_vec().clone();
+    for group in groups.iter() {
+        for path in bucket_load_shared(&deps.storage, group)?.to_vec() {
+            if !paths.contains(path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    paths.sort();
+
+    let mut files: Vec<SharedFile> = Vec::new();
+    for path in paths.into_iter() {
+        if let Some(start) = &start_after {
+            if path <= *start {
+                continue;
+            }
+        }
+        if files.len() >= limit {
+            break;
+        }
+
+        let namespace = match get_namespace_from_path(deps, &path) {
+            Ok(namespace) => namespace,
+            Err(_) => continue,
+        };
+        let f = match bucket_load_readonly_file(&deps.storage, &path, &namespace) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        if f.get_owner() == address {
+            continue;
+        }
+        if let Some(level) = f.level(&address, &groups, &now) {
+            files.push(SharedFile { path, level });
         }
     }
+
+    Ok(SharedWithMeResponse { files })
 }
 
 //This previously returned a wallet with init = false and namespace = "empty", but this is illogical so we will just return a NotFound error.
@@ -866,30 +2538,29 @@ pub fn query_wallet_info<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     behalf: &HumanAddr,
 ) -> StdResult<WalletInfoResponse> {
-    let address = behalf.as_str();
-    let load_bucket: Result<WalletInfo, StdError> =
-        bucket_read(WALLET_INFO_LOCATION, &deps.storage).load(address.as_bytes());
+    let wallet_info = load_wallet(&deps.storage, &deps.api, behalf.as_str())?;
 
-    match load_bucket {
-        Ok(wallet_info) => Ok(WalletInfoResponse {
-            init: wallet_info.init,
-            namespace: wallet_info.namespace,
-            counter: wallet_info.counter,
-        }),
-        Err(_e) => Err(StdError::NotFound { kind: String::from("Wallet not found."), backtrace: None })
-    }
+    Ok(WalletInfoResponse {
+        init: wallet_info.init,
+        namespace: wallet_info.namespace,
+        counter: wallet_info.counter,
+    })
 }
 
-pub fn try_change_owner<S: Storage, A: Api, Q: Querier>(
+//Offers ownership of path to new_owner. Nothing changes hands until they accept, and the offer can be
+//cancelled until then. Proposing again replaces the earlier offer
+pub fn try_propose_owner<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     path: String,
     message: String,
     new_owner: String,
 ) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
     let signer = deps
         .api
         .human_address(&deps.api.canonical_address(&env.message.sender)?)?;
+    let new_owner = normalize(&deps.api, &new_owner)?;
 
     //if alice now wants to give ownership of the file back to anyone, she would have to pass in the namespace of anyone
     //the only way to get the namespace of the file owner, is from the passed in path
@@ -897,23 +2568,81 @@ pub fn try_change_owner<S: Storage, A: Api, Q: Querier>(
 
     let mut f = bucket_load_file(&mut deps.storage, &path, &full_namespace)?;
 
-    if f.can_write(signer.to_string()){
+    if !has_permission(&deps.storage, &path, &full_namespace, &f, signer.to_string(), PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to change owner")).into());
+    }
+    if f.owner == new_owner {
+        return Err(ContractError::AlreadyExists(format!("{} already owns {}", new_owner, path)).into());
+    }
 
-        f.change_owner(new_owner.to_string());
-        let recipient = HumanAddr::from(String::from(new_owner));
-        let sent_message = send_message(deps, &env, recipient , &message);
+    f.propose_owner(new_owner.to_string());
+    let recipient = HumanAddr::from(String::from(new_owner));
+    let sent_message = send_message(deps, &env, recipient , &message);
 
-        match sent_message{
-            Ok(_) => (),
-            Err(_) => return Err(StdError::NotFound { kind: String::from("recipient does not exist"), backtrace: None }),
-        }
-        
-        bucket_save_file(&mut deps.storage, &path, &f, &full_namespace);
+    match sent_message{
+        Ok(_) => (),
+        Err(_) => return Err(ContractError::NotFound(String::from("recipient does not exist")).into()),
+    }
+
+    bucket_save_file(&mut deps.storage, &path, &f, &full_namespace, &now)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_accept_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let full_namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace not found!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &full_namespace)?;
+
+    if f.pending_owner() != Some(&sender_address(&deps.api, &env)?) {
+        return Err(ContractError::Unauthorized(String::from("Ownership of this file has not been offered to you")).into());
+    }
+
+    f.accept_ownership();
+    bucket_save_file(&mut deps.storage, &path, &f, &full_namespace, &now)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_decline_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let full_namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace not found!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &full_namespace)?;
+
+    if f.pending_owner() != Some(&sender_address(&deps.api, &env)?) {
+        return Err(ContractError::Unauthorized(String::from("Ownership of this file has not been offered to you")).into());
+    }
+
+    f.clear_pending_owner();
+    bucket_save_file(&mut deps.storage, &path, &f, &full_namespace, &now)?;
+    Ok(HandleResponse::default())
+}
+
+//Withdraws an offer that hasn't been accepted yet. Takes the same level as making the offer
+pub fn try_cancel_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    path: String,
+) -> StdResult<HandleResponse> {
+    let now = BlockTime::from_env(&env);
+    let full_namespace = get_namespace_from_path(deps, &path).unwrap_or(String::from("namespace not found!"));
+    let mut f = bucket_load_file(&mut deps.storage, &path, &full_namespace)?;
+
+    if !has_permission(&deps.storage, &path, &full_namespace, &f, sender_address(&deps.api, &env)?, PermType::MANAGE, &now) {
+        return Err(ContractError::Unauthorized(String::from("Unauthorized to cancel the ownership transfer")).into());
     }
-    else {
-        return Err(StdError::GenericErr { msg: "Unauthorized to change owner".to_string(), backtrace: None });
+    if f.pending_owner().is_none() {
+        return Err(ContractError::InvalidRequest(String::from("No ownership transfer is pending for this file")).into());
     }
 
+    f.clear_pending_owner();
+    bucket_save_file(&mut deps.storage, &path, &f, &full_namespace, &now)?;
     Ok(HandleResponse::default())
 }
 
@@ -922,12 +2651,10 @@ pub fn get_namespace_from_path<S: Storage, A: Api, Q: Querier>(
     path: &String,
 ) -> StdResult<String> {
 
-    let split = path.split('/');
-    let vec = split.collect::<Vec<&str>>();
-    let namespace_owner = vec[0].to_string();
-    let counter = get_counter(&deps.storage, &namespace_owner)?.to_string();
-    let full_namespace = format!("{}{}", namespace_owner, counter);
-    Ok(full_namespace)
+    //the namespace saved in the wallet rather than one worked out from its counter, so wallets that haven't
+    //been through MigrateStorage yet still find their files
+    let namespace_owner = Path::parse(path)?.root().to_string();
+    get_namespace(&deps.storage, &deps.api, &namespace_owner)
 
 }
 
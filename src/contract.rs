@@ -4,22 +4,35 @@
 // use std::ptr::null;
 
 use cosmwasm_std::{
-    debug_print, to_binary, Api, Binary, Env, Extern, HandleResponse, InitResponse, Querier,
-    QueryResult, StdError, StdResult, Storage, 
+    debug_print, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier,
+    QueryResult, StdResult, Storage, 
 };
 use secret_toolkit::crypto::sha_256;
 use std::cmp;
 
+use crate::address::{canonicalize, normalize};
 use crate::backend::{
-    query_file, query_wallet_info, try_allow_read, try_allow_write, try_change_owner,
-    try_create_file, try_create_multi_files, try_create_viewing_key, try_disallow_read,
-    try_disallow_write, try_forget_me, try_init, try_move_file, try_move_multi_files,
-    try_remove_file, try_remove_multi_files, try_reset_read, try_reset_write, try_you_up_bro,
+    last_block, query_check_access, query_file, query_folder_contents, query_folder_tree,
+    query_permissions, query_public_contents, query_shared_with_me, query_stat,
+    query_version_contents, query_versions, query_viewing_keys, query_wallet_info, save_last_block,
+    try_accept_ownership, try_allow_read, try_allow_write, try_cancel_ownership, try_comment, try_copy_file,
+    try_copy_multi_files, try_create_file, try_create_folder, try_create_multi_files,
+    try_create_viewing_key, try_decline_ownership, try_disallow_read, try_disallow_write,
+    try_forget_me, try_grant, try_init, try_make_private, try_migrate_storage, try_make_public,
+    try_move_file, try_move_multi_files, try_propose_owner, try_remove_file, try_remove_folder,
+    try_remove_multi_files, try_reset_read, try_reset_write, try_revert_file, try_revoke,
+    try_revoke_viewing_key, try_set_inheritance, try_set_retention, try_set_viewing_key,
+    try_you_up_bro, upgrade_legacy_path,
 };
+use crate::error::ContractError;
+use crate::groups::{try_add_group_members, try_create_group, try_delete_group, try_remove_group_members};
 use crate::messaging::{query_messages, send_message, clear_all_messages};
-use crate::msg::{HandleMsg, InitMsg, QueryMsg};
+use crate::msg::{HandleMsg, InitMsg, QueryMsg, QueryWithPermit};
 use crate::nodes::{claim, get_node, get_node_size, pub_query_coins, push_node, set_node_size};
-use crate::state::{read_viewing_key, save, State, CONFIG_KEY};
+use crate::path::Path;
+use crate::permit::{try_revoke_permit, validate_permit, Permit};
+use crate::scoped_key::{find_scoped_key, try_create_scoped_key, try_revoke_scoped_key};
+use crate::state::{ensure_permit_config, read_viewing_keys, save, State, CONFIG_KEY};
 use crate::viewing_key::VIEWING_KEY_SIZE;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -36,11 +49,12 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         prng_seed: sha_256(base64::encode(msg.prng_seed).as_bytes()).to_vec(),
     };
 
-    set_node_size(&mut deps.storage, 0);
+    set_node_size(&mut deps.storage, 0)?;
 
     debug_print!("Contract was initialized by {}", env.message.sender);
 
     save(&mut deps.storage, CONFIG_KEY, &config)?;
+    ensure_permit_config(&mut deps.storage, &env)?;
     Ok(InitResponse::default())
 }
 
@@ -49,42 +63,90 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
+    for path in msg.get_paths() {
+        Path::parse(path)?;
+    }
+    for path in msg.get_paths() {
+        upgrade_legacy_path(deps, &env, path)?;
+    }
+    save_last_block(&mut deps.storage, &env)?;
+    ensure_permit_config(&mut deps.storage, &env)?;
+
     match msg {
         HandleMsg::InitAddress { contents_list, path_list, entropy } => try_init(deps, env, contents_list, path_list, entropy),
         HandleMsg::Create {
             contents,
-            path
-        } => try_create_file(deps, &env, &contents, path),
+            path,
+            content_type
+        } => try_create_file(deps, &env, &contents, path, content_type),
         HandleMsg::CreateMulti {
             contents_list,
             path_list
         } => try_create_multi_files(deps, env, contents_list, path_list),
+        HandleMsg::CreateFolder {
+            contents,
+            path
+        } => try_create_folder(deps, &env, &contents, path),
         HandleMsg::Remove { path } => try_remove_file(deps, &env, path),
         HandleMsg::RemoveMulti { path_list } => try_remove_multi_files(deps, env, path_list),
+        HandleMsg::RemoveFolder { path, recursive } => try_remove_folder(deps, &env, path, recursive),
         HandleMsg::MoveMulti {
             old_path_list,
             new_path_list,
-        } => try_move_multi_files(deps, env, old_path_list, new_path_list),
-        HandleMsg::Move { old_path, new_path } => try_move_file(deps, &env, old_path, new_path),
-        HandleMsg::CreateViewingKey { entropy, .. } => try_create_viewing_key(deps, env, entropy),
-        HandleMsg::AllowRead { path, message, address_list } => {
-            try_allow_read(deps, env, path, message, address_list)
+            drop_acl,
+        } => try_move_multi_files(deps, env, old_path_list, new_path_list, drop_acl.unwrap_or(false)),
+        HandleMsg::Move { old_path, new_path, drop_acl } => try_move_file(deps, &env, old_path, new_path, drop_acl.unwrap_or(false)),
+        HandleMsg::Copy { src, dst, recursive } => try_copy_file(deps, &env, src, dst, recursive),
+        HandleMsg::CopyMulti { src_list, dst_list, recursive } => try_copy_multi_files(deps, env, src_list, dst_list, recursive),
+        HandleMsg::RevertFile { path, version } => try_revert_file(deps, &env, path, version),
+        HandleMsg::SetRetention { path, versions } => try_set_retention(deps, &env, path, versions),
+        HandleMsg::MakePublic { path } => try_make_public(deps, &env, path),
+        HandleMsg::MakePrivate { path } => try_make_private(deps, &env, path),
+        HandleMsg::SetInheritance { path, inherit } => try_set_inheritance(deps, &env, path, inherit),
+        HandleMsg::CreateViewingKey { entropy, name, .. } => try_create_viewing_key(deps, env, entropy, name),
+        HandleMsg::SetViewingKey { key, name, .. } => try_set_viewing_key(deps, env, key, name),
+        HandleMsg::RevokeViewingKey { name } => try_revoke_viewing_key(deps, env, name),
+        HandleMsg::AllowRead { path, message, address_list, expires } => {
+            try_allow_read(deps, env, path, message, address_list, expires)
         }
         HandleMsg::DisallowRead { path, message, notify, address_list } => {
             try_disallow_read(deps, env, path, message, notify, address_list)
         }
         HandleMsg::ResetRead { path, message, notify } => try_reset_read(deps, env, path, message, notify),
-        HandleMsg::AllowWrite { path, message, address_list } => {
-            try_allow_write(deps, env, path, message, address_list)
+        HandleMsg::AllowWrite { path, message, address_list, expires } => {
+            try_allow_write(deps, env, path, message, address_list, expires)
         }
         HandleMsg::DisallowWrite { path, message, notify, address_list } => {
             try_disallow_write(deps, env, path, message, notify, address_list)
         }
         HandleMsg::ResetWrite { path, message, notify } => try_reset_write(deps, env, path, message, notify),
+        HandleMsg::Grant { path, message, address_list, level, expires } => {
+            try_grant(deps, env, path, message, address_list, level, expires)
+        }
+        HandleMsg::Revoke { path, message, notify, address_list } => {
+            try_revoke(deps, env, path, message, notify, address_list)
+        }
+        HandleMsg::Comment { path, contents } => try_comment(deps, env, path, contents),
         HandleMsg::InitNode { ip, address } => try_init_node(deps, ip, address),
         HandleMsg::ClaimReward { path, key, address } => claim(deps, path, key, address),
         HandleMsg::ForgetMe { .. } => try_forget_me(deps, env),
-        HandleMsg::ChangeOwner { path, message, new_owner } => try_change_owner(deps, env, path, message, new_owner),
+        HandleMsg::MigrateStorage { paths } => try_migrate_storage(deps, env, paths),
+        HandleMsg::ProposeOwner { path, message, new_owner } => try_propose_owner(deps, env, path, message, new_owner),
+        HandleMsg::AcceptOwnership { path } => try_accept_ownership(deps, env, path),
+        HandleMsg::DeclineOwnership { path } => try_decline_ownership(deps, env, path),
+        HandleMsg::CancelOwnership { path } => try_cancel_ownership(deps, env, path),
+        // Groups
+        HandleMsg::CreateGroup { name, members } => try_create_group(deps, env, name, members),
+        HandleMsg::AddGroupMembers { name, members } => try_add_group_members(deps, env, name, members),
+        HandleMsg::RemoveGroupMembers { name, members } => try_remove_group_members(deps, env, name, members),
+        HandleMsg::DeleteGroup { name } => try_delete_group(deps, env, name),
+        // Permits
+        HandleMsg::RevokePermit { permit_name } => try_revoke_permit(deps, env, permit_name),
+        // Scoped keys
+        HandleMsg::CreateScopedKey { name, entropy, path_prefix, permissions, expires } => {
+            try_create_scoped_key(deps, env, name, entropy, path_prefix, permissions, expires)
+        }
+        HandleMsg::RevokeScopedKey { name } => try_revoke_scoped_key(deps, env, name),
         // Messaging
         HandleMsg::SendMessage { to, contents } => send_message(deps, &env, to, &contents),
         HandleMsg::DeleteAllMessages {} => clear_all_messages(deps, env),
@@ -97,10 +159,12 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::YouUpBro { address } => to_binary(&try_you_up_bro(deps, address)?),
+        QueryMsg::GetPublicContents { path } => to_binary(&query_public_contents(deps, path)?),
         QueryMsg::GetNodeCoins { address } => to_binary(&pub_query_coins(deps, address)?),
         QueryMsg::GetNodeIP { index } => to_binary(&try_get_ip(deps, index)?),
         QueryMsg::GetNodeList { size } => to_binary(&try_get_top_x(deps, size)?),
         QueryMsg::GetNodeListSize {} => to_binary(&try_get_node_list_size(deps)?),
+        QueryMsg::WithPermit { permit, query } => permit_queries(deps, permit, query),
         _ => authenticated_queries(deps, msg),
     }
 }
@@ -109,35 +173,111 @@ fn authenticated_queries<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
 ) -> QueryResult {
-    let (addresses, key) = msg.get_validation_params();
+    let (addresses, key) = msg.get_validation_params()?;
 
+    //behalf as stored records spell it, once the key has been checked
+    let mut authorized: Option<HumanAddr> = None;
     for address in addresses {
         let canonical_addr = deps.api.canonical_address(address)?;
 
-        let expected_key = read_viewing_key(&deps.storage, &canonical_addr);
+        let expected_keys = read_viewing_keys(&deps.storage, &canonical_addr)?;
 
-        if expected_key.is_none() {
+        if expected_keys.is_empty() {
             // Checking the key will take significant time. We don't want to exit immediately if it isn't set
             // in a way which will allow to time the command and determine if a viewing key doesn't exist
             key.check_viewing_key(&[0u8; VIEWING_KEY_SIZE]);
-        } else if key.check_viewing_key(expected_key.unwrap().as_slice()) {
-            return match msg {
-                QueryMsg::GetContents { path, behalf, .. } => {
-                    to_binary(&query_file(deps, path, &behalf)?)
-                }
-                QueryMsg::GetWalletInfo { behalf, .. } => {
-                    to_binary(&query_wallet_info(deps, &behalf)?)
-                }
-                QueryMsg::GetMessages { behalf, .. } => to_binary(&query_messages(deps, &behalf)?),
-                _ => panic!("How did this even get to this stage. It should have been processed."),
-            };
+        } else {
+            // Any of the wallet's keys will do. All of them get checked, so the time taken doesn't say which one matched
+            let mut matched = false;
+            for expected_key in expected_keys.iter() {
+                matched |= key.check_viewing_key(expected_key.hashed_key.as_slice());
+            }
+            if matched {
+                authorized = Some(HumanAddr(normalize(&deps.api, address.as_str())?));
+                break;
+            }
+        }
+
+        // Not the main key, but it may be one of the scoped keys handed out to apps
+        if let Some(scoped) = find_scoped_key(&deps.storage, &canonical_addr, &key)? {
+            scoped.check(&msg.permission()?, msg.path(), &last_block(&deps.storage))?;
+            authorized = Some(HumanAddr(normalize(&deps.api, address.as_str())?));
+            break;
         }
     }
 
-    Err(StdError::NotFound {
-        kind: String::from("Your viewing key does not match 'behalf' address."),
-        backtrace: None,
-    })
+    let behalf = match authorized {
+        Some(behalf) => behalf,
+        None => return Err(ContractError::Unauthorized(String::from("Your viewing key does not match 'behalf' address.")).into()),
+    };
+
+    match msg {
+        QueryMsg::GetContents { path, .. } => {
+            to_binary(&query_file(deps, path, &behalf)?)
+        }
+        QueryMsg::GetFolderContents { path, .. } => {
+            to_binary(&query_folder_contents(deps, path, &behalf)?)
+        }
+        QueryMsg::Stat { path, .. } => {
+            to_binary(&query_stat(deps, path, &behalf)?)
+        }
+        QueryMsg::GetVersions { path, .. } => {
+            to_binary(&query_versions(deps, path, &behalf)?)
+        }
+        QueryMsg::GetVersionContents { path, version, .. } => {
+            to_binary(&query_version_contents(deps, path, &behalf, version)?)
+        }
+        QueryMsg::GetFolderTree { path, max_depth, max_entries, .. } => {
+            to_binary(&query_folder_tree(deps, path, &behalf, max_depth, max_entries)?)
+        }
+        QueryMsg::GetSharedWithMe { start_after, limit, .. } => {
+            to_binary(&query_shared_with_me(deps, &behalf, start_after, limit)?)
+        }
+        QueryMsg::GetPermissions { path, .. } => {
+            to_binary(&query_permissions(deps, path, &behalf)?)
+        }
+        QueryMsg::CheckAccess { path, address, level, .. } => {
+            to_binary(&query_check_access(deps, path, &behalf, address, level)?)
+        }
+        QueryMsg::GetWalletInfo { .. } => {
+            to_binary(&query_wallet_info(deps, &behalf)?)
+        }
+        QueryMsg::GetViewingKeys { .. } => to_binary(&query_viewing_keys(deps, &behalf)?),
+        QueryMsg::GetMessages { .. } => to_binary(&query_messages(deps, &behalf)?),
+        _ => Err(ContractError::InvalidRequest(String::from("This query type does not require authentication")).into()),
+    }
+}
+
+//Same queries as authenticated_queries, with behalf taken from the permit's signature instead of checked against a viewing key
+fn permit_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    permit: Permit,
+    query: QueryWithPermit,
+) -> QueryResult {
+    let behalf = validate_permit(&deps.storage, &deps.api, &permit, query.permission())?;
+
+    match query {
+        QueryWithPermit::GetContents { path } => to_binary(&query_file(deps, path, &behalf)?),
+        QueryWithPermit::GetFolderContents { path } => to_binary(&query_folder_contents(deps, path, &behalf)?),
+        QueryWithPermit::Stat { path } => to_binary(&query_stat(deps, path, &behalf)?),
+        QueryWithPermit::GetVersions { path } => to_binary(&query_versions(deps, path, &behalf)?),
+        QueryWithPermit::GetVersionContents { path, version } => {
+            to_binary(&query_version_contents(deps, path, &behalf, version)?)
+        }
+        QueryWithPermit::GetFolderTree { path, max_depth, max_entries } => {
+            to_binary(&query_folder_tree(deps, path, &behalf, max_depth, max_entries)?)
+        }
+        QueryWithPermit::GetSharedWithMe { start_after, limit } => {
+            to_binary(&query_shared_with_me(deps, &behalf, start_after, limit)?)
+        }
+        QueryWithPermit::GetPermissions { path } => to_binary(&query_permissions(deps, path, &behalf)?),
+        QueryWithPermit::CheckAccess { path, address, level } => {
+            to_binary(&query_check_access(deps, path, &behalf, address, level)?)
+        }
+        QueryWithPermit::GetWalletInfo {} => to_binary(&query_wallet_info(deps, &behalf)?),
+        QueryWithPermit::GetViewingKeys {} => to_binary(&query_viewing_keys(deps, &behalf)?),
+        QueryWithPermit::GetMessages {} => to_binary(&query_messages(deps, &behalf)?),
+    }
 }
 
 fn try_init_node<S: Storage, A: Api, Q: Querier>(
@@ -145,7 +285,8 @@ fn try_init_node<S: Storage, A: Api, Q: Querier>(
     ip: String,
     address: String,
 ) -> StdResult<HandleResponse> {
-    push_node(&mut deps.storage, ip, address);
+    let address = canonicalize(&deps.api, &address)?;
+    push_node(&mut deps.storage, ip, address)?;
 
     Ok(HandleResponse {
         messages: vec![],
@@ -161,7 +302,7 @@ fn try_get_ip<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse {
         messages: vec![],
         log: vec![],
-        data: Some(to_binary(&get_node(&deps.storage, index))?),
+        data: Some(to_binary(&get_node(&deps.storage, index)?)?),
     })
 }
 
@@ -169,9 +310,9 @@ fn try_get_top_x<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     size: u64,
 ) -> StdResult<HandleResponse> {
-    let size = cmp::min(size, get_node_size(&deps.storage));
+    let size = cmp::min(size, get_node_size(&deps.storage)?);
 
-    let index_node = &get_node(&deps.storage, 0);
+    let index_node = &get_node(&deps.storage, 0)?;
 
     let mut nodes = vec![index_node.clone()];
 
@@ -185,7 +326,7 @@ fn try_get_top_x<S: Storage, A: Api, Q: Querier>(
 
     let mut x = 1;
     while x < size {
-        let new_node = &get_node(&deps.storage, x);
+        let new_node = &get_node(&deps.storage, x)?;
         nodes.push(new_node.clone());
         x += 1;
     }
@@ -203,7 +344,7 @@ fn try_get_node_list_size<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse {
         messages: vec![],
         log: vec![],
-        data: Some(to_binary(&get_node_size(&deps.storage))?),
+        data: Some(to_binary(&get_node_size(&deps.storage)?)?),
     })
 }
 
@@ -325,6 +466,7 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let create_vk_msg = HandleMsg::CreateViewingKey {
             entropy: "supbro".to_string(),
+            name: None,
             padding: None,
         };
         let handle_response = handle(&mut deps, env, create_vk_msg).unwrap();
@@ -385,7 +527,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("I'm lonely"),
-            path: String::from("anyone/memes/pepe.jpg")
+            path: String::from("anyone/memes/pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -393,7 +536,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("Abdul"),
-            path: String::from("DoesNotExist/crazy_man.jpg")
+            path: String::from("DoesNotExist/crazy_man.jpg"),
+            content_type: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -403,7 +547,8 @@ mod tests {
         let env = mock_env("Dave", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("Hasbullah"),
-            path: String::from("anyone/memes/silly_man.jpg")
+            path: String::from("anyone/memes/silly_man.jpg"),
+            content_type: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -452,6 +597,7 @@ mod tests {
             path: String::from("anyone/memes/pepe.jpg"),
             message: String::from("anyone has given you read access to [ anyone/memes/pepe.jpg ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -530,7 +676,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("I'm sad"),
-            path: String::from("anyone/pepe.jpg")
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -538,7 +685,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("I'm lonely"),
-            path: String::from("anyone/hasbullah.jpg")
+            path: String::from("anyone/hasbullah.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -546,7 +694,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("I'm happy now :)"),
-            path: String::from("anyone/sunshine.jpg")
+            path: String::from("anyone/sunshine.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -616,18 +765,25 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("King pepe"),
-            path: String::from("anyone/King_pepe.jpg")
+            path: String::from("anyone/King_pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/King_pepe.jpg"),
             message: String::from("anyone has given you ownership of anyone/King_pepe.jpg"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/King_pepe.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // anyone tries to remove a file that doesn't belong to them anymore. Will fail
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Remove {
@@ -675,7 +831,7 @@ mod tests {
 
         // Create folder test/
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::Create {
+        let msg = HandleMsg::CreateFolder {
             contents: String::from("<content inside test/ folder>"),
             path: String::from("anyone/test/")
         };
@@ -753,9 +909,9 @@ mod tests {
         let mut deps = mock_dependencies(20, &[]);
         let vk = init_for_test(&mut deps, String::from("anyone"));
 
-        // Create File
+        // Create Folder
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::Create {
+        let msg = HandleMsg::CreateFolder {
             contents: String::from("content of meme/ folder "),
             path: String::from("anyone/meme/")
         };
@@ -825,7 +981,8 @@ mod tests {
         .unwrap();
         let value: WalletInfoResponse = from_binary(&query_res).unwrap();
         assert_eq!(value.init, false);
-        assert_eq!(value.namespace, "anyone1".to_string());
+        let canonical = deps.api.canonical_address(&HumanAddr("anyone".to_string())).unwrap();
+        assert_eq!(value.namespace, crate::backend::namespace_for(&canonical, 1));
         assert_eq!(value.counter, 1);
         println!("{:#?}", value);
     }
@@ -836,20 +993,18 @@ mod tests {
         let vk = init_for_test(&mut deps, String::from("anyone"));
 
         // Create 3 folders (test/ meme_folder/ pepe/)
-        let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::CreateMulti {
-            contents_list: vec![
-                String::from("<content inside test/>"),
-                String::from("<content inside meme_folder/>"),
-                String::from("<content inside pepe/>"),
-            ],
-            path_list: vec![
-                String::from("anyone/test/"),
-                String::from("anyone/meme_folder/"),
-                String::from("anyone/pepe/"),
-            ]
-        };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        for (contents, path) in vec![
+            ("<content inside test/>", "anyone/test/"),
+            ("<content inside meme_folder/>", "anyone/meme_folder/"),
+            ("<content inside pepe/>", "anyone/pepe/"),
+        ] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::CreateFolder {
+                contents: String::from(contents),
+                path: String::from(path),
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
 
         // Create 2 Files phrog1.png and phrog2.png
         let env = mock_env("anyone", &[]);
@@ -867,6 +1022,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/phrog1.png"),
             new_path: String::from("anyone/meme_folder/phrog1.png"),
+            drop_acl: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -890,6 +1046,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/meme_folder/phrog1.png"),
             new_path: String::from("anyone/test/phrog1.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -900,6 +1057,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/phrog2.png"),
             new_path: String::from("anyone/doesnt_exist/phrog2.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -930,6 +1088,7 @@ mod tests {
                 String::from("anyone/pepe/pepe1.png"),
                 String::from("anyone/pepe/pepe2.png"),
             ],
+            drop_acl: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -0,0 +1,73 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use cosmwasm_std::{from_slice, to_vec, StdError};
+
+/**
+  Every error the contract raises on purpose. Each one goes out as a GenericErr whose message is the JSON of an
+  ErrorResponse, so clients can branch on code and show message. Codes are part of the API and must not change.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractError {
+    // the path, wallet, version, group or key asked for doesn't exist
+    NotFound(String),
+    // the sender, viewing key or permit isn't allowed to do this
+    Unauthorized(String),
+    AlreadyExists(String),
+    InvalidPath(String),
+    // the message is well formed but asks for something that can't be done
+    InvalidRequest(String),
+    // a limit on counts or sizes would be passed
+    QuotaExceeded(String),
+    // storage is in a state the contract didn't expect
+    Internal(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ErrorResponse {
+    pub code: String,
+    pub message: String,
+}
+
+impl ContractError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContractError::NotFound(_) => "not_found",
+            ContractError::Unauthorized(_) => "unauthorized",
+            ContractError::AlreadyExists(_) => "already_exists",
+            ContractError::InvalidPath(_) => "invalid_path",
+            ContractError::InvalidRequest(_) => "invalid_request",
+            ContractError::QuotaExceeded(_) => "quota_exceeded",
+            ContractError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ContractError::NotFound(message)
+            | ContractError::Unauthorized(message)
+            | ContractError::AlreadyExists(message)
+            | ContractError::InvalidPath(message)
+            | ContractError::InvalidRequest(message)
+            | ContractError::QuotaExceeded(message)
+            | ContractError::Internal(message) => message,
+        }
+    }
+}
+
+impl From<ContractError> for StdError {
+    fn from(error: ContractError) -> Self {
+        let response = ErrorResponse { code: error.code().to_string(), message: error.message().to_string() };
+        match to_vec(&response) {
+            Ok(json) => StdError::generic_err(String::from_utf8_lossy(&json)),
+            Err(_) => StdError::generic_err(error.message()),
+        }
+    }
+}
+
+//The code and message of an error raised through ContractError, or None for errors that came from somewhere else
+pub fn parse_error(error: &StdError) -> Option<ErrorResponse> {
+    match error {
+        StdError::GenericErr { msg, .. } => from_slice(msg.as_bytes()).ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,299 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket};
+use cosmwasm_std::{Api, Env, Extern, HandleResponse, Querier, StdResult, Storage};
+
+use crate::address::{normalize, sender_address};
+use crate::error::ContractError;
+use crate::ordered_set::OrderedSet;
+
+static GROUPS_LOCATION: &[u8] = b"GROUPS";
+static GROUP_MEMBERSHIPS_LOCATION: &[u8] = b"GROUP_MEMBERSHIPS";
+static GROUP_GENERATIONS_LOCATION: &[u8] = b"GROUP_GENERATIONS";
+
+/*
+  A grant can name a group in place of an address:
+    group:<name>          one of the sender's own groups
+    group:<owner>/<name>  a group belonging to someone else
+  Grants always store the full form, so they keep pointing at the same group whoever changes the list later.
+  Only groups of the file's owner or of the sender can be granted anything.
+
+  A group that gets deleted and created again under the same name is a new group, and grants given to the old one
+  shouldn't carry over to it. Every time a name is reused its generation goes up, and from the second generation on
+  the full form ends in #<generation>, e.g. group:<owner>/<name>#1. Grants and memberships store that form, so grants
+  naming an older generation don't match anyone anymore.
+*/
+pub const GROUP_PREFIX: &str = "group:";
+pub const GENERATION_SEPARATOR: char = '#';
+pub const MAX_GROUP_NAME_LENGTH: usize = 64;
+pub const MAX_GROUP_MEMBERS: usize = 100;
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct Group {
+    pub owner: String,
+    pub name: String,
+    pub members: OrderedSet<String>,
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl Group {
+    pub fn new(owner: String, name: String) -> Group {
+        Group {
+            owner,
+            name,
+            members: OrderedSet::<String>::new(),
+            generation: 0,
+        }
+    }
+
+    //the name grants store for this group
+    pub fn reference(&self) -> String {
+        match self.generation {
+            0 => group_reference(&self.owner, &self.name),
+            generation => format!("{}{}{}", group_reference(&self.owner, &self.name), GENERATION_SEPARATOR, generation),
+        }
+    }
+}
+
+pub fn group_reference(owner: &str, name: &str) -> String {
+    format!("{}{}/{}", GROUP_PREFIX, owner, name)
+}
+
+pub fn is_group(grantee: &str) -> bool {
+    grantee.starts_with(GROUP_PREFIX)
+}
+
+//Turns an entry of an address_list into what gets stored on the file: addresses are left alone and groups get their owner spelled out
+pub fn normalize_grantee(sender: &str, grantee: &str) -> String {
+    match grantee.strip_prefix(GROUP_PREFIX) {
+        Some(name) if !name.contains('/') => group_reference(sender, name),
+        _ => grantee.to_string(),
+    }
+}
+
+//Splits a full group reference into owner and name, leaving out the generation
+fn parse_reference(reference: &str) -> Option<(&str, &str)> {
+    let rest = reference.strip_prefix(GROUP_PREFIX)?;
+    let split = rest.find('/')?;
+    let name = &rest[split + 1..];
+    let name = match name.find(GENERATION_SEPARATOR) {
+        Some(end) => &name[..end],
+        None => name,
+    };
+    Some((&rest[..split], name))
+}
+
+pub fn load_group<S: Storage>(store: &S, owner: &str, name: &str) -> StdResult<Option<Group>> {
+    ReadonlyBucket::multilevel(&[GROUPS_LOCATION, owner.as_bytes()], store).may_load(name.as_bytes())
+}
+
+//Like normalize_grantee, but addresses, group owners included, have to be valid and get stored in their normalized form.
+//A group named without a generation means its current one, or the first if it doesn't exist anymore
+pub fn validate_grantee<S: Storage, A: Api>(api: &A, store: &S, sender: &str, grantee: &str) -> StdResult<String> {
+    if !is_group(grantee) {
+        return normalize(api, grantee);
+    }
+    let reference = normalize_grantee(sender, grantee);
+    let (owner, name) = match parse_reference(&reference) {
+        Some((owner, name)) => (normalize(api, owner)?, name),
+        None => return Err(ContractError::InvalidRequest(format!("'{}' is not a valid group", grantee)).into()),
+    };
+    if let Some(split) = reference.find(GENERATION_SEPARATOR) {
+        return match reference[split + 1..].parse::<u64>() {
+            Ok(generation) => {
+                let mut group = Group::new(owner, name.to_string());
+                group.generation = generation;
+                Ok(group.reference())
+            }
+            Err(_) => Err(ContractError::InvalidRequest(format!("'{}' is not a valid group", grantee)).into()),
+        };
+    }
+    match load_group(store, &owner, name)? {
+        Some(group) => Ok(group.reference()),
+        None => Ok(group_reference(&owner, name)),
+    }
+}
+
+//Like validate_grantee, but a group also has to exist and belong to the file's owner or the sender to be granted anything
+pub fn resolve_grantee<S: Storage, A: Api>(api: &A, store: &S, sender: &str, file_owner: &str, grantee: &str) -> StdResult<String> {
+    let grantee = validate_grantee(api, store, sender, grantee)?;
+    if !is_group(&grantee) {
+        return Ok(grantee);
+    }
+    if let Some((owner, _)) = parse_reference(&grantee) {
+        if owner != sender && owner != normalize(api, file_owner)? {
+            return Err(ContractError::Unauthorized(format!("Group {} belongs to neither the file's owner nor you", grantee)).into());
+        }
+    }
+    if load_referenced_group(store, &grantee)?.is_none() {
+        return Err(ContractError::NotFound(format!("Group {}", grantee)).into());
+    }
+    Ok(grantee)
+}
+
+//The group a full reference points to, if it still exists and is still the same generation
+pub fn load_referenced_group<S: Storage>(store: &S, reference: &str) -> StdResult<Option<Group>> {
+    match parse_reference(reference) {
+        Some((owner, name)) => Ok(load_group(store, owner, name)?.filter(|group| group.reference() == reference)),
+        None => Ok(None),
+    }
+}
+
+//Every group address is a member of, as full references
+pub fn groups_of<S: Storage>(store: &S, address: &str) -> Vec<String> {
+    let memberships: Option<OrderedSet<String>> = bucket_read(GROUP_MEMBERSHIPS_LOCATION, store)
+        .may_load(address.as_bytes())
+        .unwrap_or(None);
+    match memberships {
+        Some(memberships) => memberships.to_vec().clone(),
+        None => vec![],
+    }
+}
+
+//The addresses behind a grantee: the members of a group, or the address itself
+pub fn grantee_addresses<S: Storage>(store: &S, grantee: &str) -> Vec<String> {
+    if !is_group(grantee) {
+        return vec![grantee.to_string()];
+    }
+    match load_referenced_group(store, grantee) {
+        Ok(Some(group)) => group.members.to_vec().clone(),
+        _ => vec![],
+    }
+}
+
+pub fn try_create_group<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    members: Vec<String>,
+) -> StdResult<HandleResponse> {
+    let owner = sender_address(&deps.api, &env)?;
+    validate_group_name(&name)?;
+
+    if load_group(&deps.storage, &owner, &name)?.is_some() {
+        return Err(ContractError::AlreadyExists(format!("Group {} already exists", name)).into());
+    }
+
+    let mut group = Group::new(owner, name);
+    group.generation = next_generation(&deps.storage, &group)?;
+    add_members(&mut deps.storage, &deps.api, &mut group, members)?;
+    save_group(&mut deps.storage, &group)?;
+
+    Ok(HandleResponse::default())
+}
+
+pub fn try_add_group_members<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    members: Vec<String>,
+) -> StdResult<HandleResponse> {
+    let mut group = load_own_group(&deps.storage, &deps.api, &env, &name)?;
+    add_members(&mut deps.storage, &deps.api, &mut group, members)?;
+    save_group(&mut deps.storage, &group)?;
+
+    Ok(HandleResponse::default())
+}
+
+pub fn try_remove_group_members<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    members: Vec<String>,
+) -> StdResult<HandleResponse> {
+    let mut group = load_own_group(&deps.storage, &deps.api, &env, &name)?;
+    for member in members.iter() {
+        let member = normalize(&deps.api, member)?;
+        group.members.remove(member.to_string());
+        remove_membership(&mut deps.storage, &member, &group.reference())?;
+    }
+    save_group(&mut deps.storage, &group)?;
+
+    Ok(HandleResponse::default())
+}
+
+//Grants naming a deleted group stay on the files, but nobody is a member of it anymore, and a group created under the
+//same name later is a new generation they don't match
+pub fn try_delete_group<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let group = load_own_group(&deps.storage, &deps.api, &env, &name)?;
+    for member in group.members.to_vec().iter() {
+        remove_membership(&mut deps.storage, member, &group.reference())?;
+    }
+    Bucket::<S, Group>::multilevel(&[GROUPS_LOCATION, group.owner.as_bytes()], &mut deps.storage).remove(name.as_bytes());
+    Bucket::multilevel(&[GROUP_GENERATIONS_LOCATION, group.owner.as_bytes()], &mut deps.storage)
+        .save(name.as_bytes(), &(group.generation + 1))?;
+
+    Ok(HandleResponse::default())
+}
+
+fn validate_group_name(name: &str) -> StdResult<()> {
+    if name.is_empty() || name.len() > MAX_GROUP_NAME_LENGTH {
+        return Err(ContractError::InvalidRequest(format!("Group names must be between 1 and {} bytes", MAX_GROUP_NAME_LENGTH)).into());
+    }
+    if name.contains('/') || name.contains(GENERATION_SEPARATOR) || name.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err(ContractError::InvalidRequest(String::from("Group names can't contain '/', '#', whitespace or control characters")).into());
+    }
+    Ok(())
+}
+
+//Groups are kept under their owner's normalized address, the same spelling grants use to refer to them
+fn load_own_group<S: Storage, A: Api>(store: &S, api: &A, env: &Env, name: &str) -> StdResult<Group> {
+    let owner = sender_address(api, env)?;
+    match load_group(store, &owner, name)? {
+        Some(group) => Ok(group),
+        None => Err(ContractError::NotFound(format!("Group {}", name)).into()),
+    }
+}
+
+fn add_members<S: Storage, A: Api>(store: &mut S, api: &A, group: &mut Group, members: Vec<String>) -> StdResult<()> {
+    for member in members.into_iter() {
+        if is_group(&member) {
+            return Err(ContractError::InvalidRequest(String::from("Groups can't contain other groups")).into());
+        }
+        let member = normalize(api, &member)?;
+        add_membership(store, &member, &group.reference())?;
+        group.members.push(member);
+    }
+    if group.members.len() > MAX_GROUP_MEMBERS {
+        return Err(ContractError::QuotaExceeded(format!("Groups can't have more than {} members", MAX_GROUP_MEMBERS)).into());
+    }
+    Ok(())
+}
+
+//The generation a group created under this name gets, one past the last deleted one
+fn next_generation<S: Storage>(store: &S, group: &Group) -> StdResult<u64> {
+    Ok(ReadonlyBucket::multilevel(&[GROUP_GENERATIONS_LOCATION, group.owner.as_bytes()], store)
+        .may_load(group.name.as_bytes())?
+        .unwrap_or(0))
+}
+
+fn save_group<S: Storage>(store: &mut S, group: &Group) -> StdResult<()> {
+    Bucket::multilevel(&[GROUPS_LOCATION, group.owner.as_bytes()], store).save(group.name.as_bytes(), group)
+}
+
+fn add_membership<S: Storage>(store: &mut S, member: &str, reference: &str) -> StdResult<()> {
+    let mut memberships: OrderedSet<String> = bucket(GROUP_MEMBERSHIPS_LOCATION, store)
+        .may_load(member.as_bytes())?
+        .unwrap_or_else(OrderedSet::new);
+    memberships.push(reference.to_string());
+    bucket(GROUP_MEMBERSHIPS_LOCATION, store).save(member.as_bytes(), &memberships)
+}
+
+fn remove_membership<S: Storage>(store: &mut S, member: &str, reference: &str) -> StdResult<()> {
+    let mut memberships_bucket = bucket::<S, OrderedSet<String>>(GROUP_MEMBERSHIPS_LOCATION, store);
+    let mut memberships = match memberships_bucket.may_load(member.as_bytes())? {
+        Some(memberships) => memberships,
+        None => return Ok(()),
+    };
+    memberships.retain(|r| r != reference);
+    if memberships.len() == 0 {
+        memberships_bucket.remove(member.as_bytes());
+        return Ok(());
+    }
+    memberships_bucket.save(member.as_bytes(), &memberships)
+}
@@ -1,9 +1,15 @@
 pub mod contract;
 pub mod msg;
 pub mod state;
+pub mod address;
 pub mod backend;
+pub mod error;
 pub mod ordered_set;
+pub mod path;
 pub mod nodes;
+pub mod groups;
+pub mod permit;
+pub mod scoped_key;
 pub mod more_tests;
 mod viewing_key;
 mod utils;
@@ -2,11 +2,13 @@ use std::convert::TryInto;
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{ Storage, HumanAddr, StdResult, StdError, HandleResponse, Api, Querier, Extern, Env, debug_print};
+use cosmwasm_std::{ Storage, HumanAddr, StdResult, HandleResponse, Api, Querier, Extern, Env, debug_print};
 use cosmwasm_storage::{ PrefixedStorage, ReadonlyPrefixedStorage};
 
 use secret_toolkit_fork::storage::{AppendStore, AppendStoreMut};
 
+use crate::address::normalize;
+use crate::error::ContractError;
 use crate::msg::MessageResponse;
 
 //Attach to message_list_counter (in wallet info) to help implement delete_all_messages()
@@ -70,7 +72,7 @@ pub fn append_message<S: Storage, A: Api, Q: Querier> (
 
     let option_error_message = format!("Provided storage doesn't seem like an AppendStore");
     let mut store = PrefixedStorage::multilevel(&[PREFIX_MSGS_RECEIVED, for_address.0.as_bytes()], &mut deps.storage);
-    let mut store = AppendStoreMut::attach(&mut store).unwrap_or(Err(StdError::generic_err(option_error_message)))?;
+    let mut store = AppendStoreMut::attach(&mut store).unwrap_or(Err(ContractError::Internal(option_error_message).into()))?;
 
     store.push(message)
 }
@@ -120,7 +122,7 @@ pub fn get_collection_owner<S: Storage, A: Api, Q: Querier>(
 
     let option_error_message = format!("Provided storage doesn't seem like an AppendStore");
     let mut store = ReadonlyPrefixedStorage::multilevel(&[PREFIX_MSGS_RECEIVED, behalf.0.as_bytes()], &deps.storage);
-    let store = AppendStore::<Message, _, _>::attach(&mut store).unwrap_or(Err(StdError::generic_err(option_error_message)))?;
+    let store = AppendStore::<Message, _, _>::attach(&mut store).unwrap_or(Err(ContractError::Internal(option_error_message).into()))?;
 
     //retrieve message at index 0 which holds the owner of the collection
     let message = store.get_at(0)?;
@@ -168,6 +170,7 @@ pub fn send_message<S: Storage, A: Api, Q: Querier>(
     contents: &String,
 ) -> StdResult<HandleResponse> {
 
+    let to = HumanAddr(normalize(&deps.api, to.as_str())?);
     let message = Message::new(String::from(contents), env.message.sender.to_string());
 
     let already_init = collection_exist(deps, &to);
@@ -210,7 +213,7 @@ pub fn query_messages<S: Storage, A: Api, Q: Querier>(
         )?;
         _messages = msgs
     } else {
-        return Err(StdError::generic_err("Can only query your own messages!"));
+        return Err(ContractError::Unauthorized(String::from("Can only query your own messages!")).into());
     }
 
     Ok(MessageResponse {messages: _messages})
@@ -225,7 +228,7 @@ pub fn clear_all_messages<S: Storage, A: Api, Q: Querier>(
 
     let option_error_message = format!("Provided storage doesn't seem like an AppendStore");            
     let mut store = PrefixedStorage::multilevel(&[PREFIX_MSGS_RECEIVED, env.message.sender.0.as_bytes()], &mut deps.storage);
-    let mut store = AppendStoreMut::<Message, _, _>::attach(&mut store).unwrap_or(Err(StdError::generic_err(option_error_message)))?;
+    let mut store = AppendStoreMut::<Message, _, _>::attach(&mut store).unwrap_or(Err(ContractError::Internal(option_error_message).into()))?;
 
     store.clear();
     
@@ -4,16 +4,33 @@ mod tests {
         Api, Extern, Querier, Storage,
     };
 
-    use crate::msg::{HandleMsg, InitMsg, QueryMsg};
+    use crate::msg::{HandleMsg, InitMsg, QueryMsg, QueryWithPermit};
 
     use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{coins, from_binary, HumanAddr};
+    use cosmwasm_std::{coins, from_binary, Binary, HandleResponse, HumanAddr, StdResult};
 
-    use crate::backend::File;
+    use crate::backend::{
+        bucket_load_readonly_file, bucket_load_shared, bucket_load_wallet, namespace_for, BlockTime, Expiration,
+        File, PermType,
+    };
     use crate::contract::{init, handle, query};
+    use crate::error::parse_error;
     use crate::messaging::Message;
-    use crate::msg::{FileResponse, HandleAnswer, MessageResponse};
+    use crate::nodes::load_node_data;
+    use crate::ordered_set::OrderedSet;
+    use crate::msg::{
+        BigTreeResponse, CheckAccessResponse, FileResponse, FolderContentsResponse, HandleAnswer, MessageResponse,
+        PermissionsResponse, PublicContentsResponse, ResponseStatus, SharedFile, SharedWithMeResponse, StatResponse,
+        VersionContentsResponse, VersionsResponse, ViewingKeysResponse, WalletInfoResponse,
+    };
+    use crate::state::{load, save, State, CONFIG_KEY};
+    use crate::permit::{pub_key_to_address, Permission, Permit, PermitParams, PermitSignature, PubKey};
     use crate::viewing_key::ViewingKey;
+    use k256::ecdsa::signature::Signer;
+    use cosmwasm_storage::{bucket, bucket_read};
+    use k256::ecdsa::{Signature, SigningKey};
+    use secret_toolkit::crypto::sha_256;
+    use serde::{Deserialize, Serialize};
 
     fn init_for_test<S: Storage, A: Api, Q: Querier>(
         deps: &mut Extern<S, A, Q>,
@@ -48,20 +65,18 @@ mod tests {
         let vk2 = init_for_test(&mut deps, String::from("alice"));
 
         // Create 3 folders (test/ meme_folder/ pepe/)
-        let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::CreateMulti {
-            contents_list: vec![
-                String::from("<content inside test/>"),
-                String::from("<content inside meme_folder/>"),
-                String::from("<content inside junior/>"),
-            ],
-            path_list: vec![
-                String::from("anyone/test/"),
-                String::from("anyone/meme_folder/"),
-                String::from("anyone/junior/"),
-            ]
-        };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        for (contents, path) in vec![
+            ("<content inside test/>", "anyone/test/"),
+            ("<content inside meme_folder/>", "anyone/meme_folder/"),
+            ("<content inside junior/>", "anyone/junior/"),
+        ] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::CreateFolder {
+                contents: String::from(contents),
+                path: String::from(path),
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
 
         // Create 2 Files bunny1.png and bunny2.png
         let env = mock_env("anyone", &[]);
@@ -76,13 +91,19 @@ mod tests {
 
         // Given ownership of bunny1.png to alice
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/test/bunny1.png"),
             message: String::from("anyone has given you ownership of anyone/test/bunny1.png"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/test/bunny1.png"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // Get bunny1 with alice's viewing key to ensure alice is now owner
         let query_res = query(
             &deps,
@@ -100,6 +121,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/bunny1.png"),
             new_path: String::from("anyone/meme_folder/bunny1.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -110,7 +132,7 @@ mod tests {
 
         // lets make a folder inside of alice's root directory to store her new bunny in
         let env = mock_env("alice", &[]);
-        let msg = HandleMsg::Create {
+        let msg = HandleMsg::CreateFolder {
             contents: "bunnys go here".to_string(),
             path: String::from("alice/bunny_home/")
         };
@@ -134,6 +156,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/bunny1.png"),
             new_path: String::from("alice/bunny_home/bunny1.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg).unwrap();
         println!(
@@ -178,33 +201,42 @@ mod tests {
         let vk2 = init_for_test(&mut deps, String::from("alice"));
 
         // Create 2 folders (test/, junior/)
-        let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::CreateMulti {
-            contents_list: vec![
-                String::from("<content inside test/>"),
-                String::from("<content inside junior/>"),
-            ],
-            path_list: vec![String::from("anyone/test/"), String::from("anyone/junior/")]
-        };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        for (contents, path) in vec![
+            ("<content inside test/>", "anyone/test/"),
+            ("<content inside junior/>", "anyone/junior/"),
+        ] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::CreateFolder {
+                contents: String::from(contents),
+                path: String::from(path),
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
 
         // Create bunny.png
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("bunny"),
-            path: String::from("anyone/test/bunny.png")
+            path: String::from("anyone/test/bunny.png"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
         // Given ownership of bunny.png to alice
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/test/bunny.png"),
             message: String::from("anyone has given you ownership of anyone/test/bunny.png"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/test/bunny.png"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // Get bunny with alice's viewing key to ensure alice is now owner
         let query_res = query(
             &deps,
@@ -222,6 +254,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/bunny.png"),
             new_path: String::from("anyone/junior/bunny.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err());
@@ -233,6 +266,7 @@ mod tests {
             path: "anyone/junior/".to_string(),
             message: String::from("anyone has given you write access to [anyone/junior/]"),
             address_list: vec!["alice".to_string()],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -253,6 +287,7 @@ mod tests {
         let msg = HandleMsg::Move {
             old_path: String::from("anyone/test/bunny.png"),
             new_path: String::from("anyone/junior/bunny.png"),
+            drop_acl: None,
         };
         let res = handle(&mut deps, env, msg).unwrap();
         println!(
@@ -296,7 +331,7 @@ mod tests {
 
         // Create Folder Test
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::Create {
+        let msg = HandleMsg::CreateFolder {
             contents: String::from("<content of test/ folder>"),
             path: String::from("anyone/test/")
         };
@@ -306,7 +341,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("I'm sad"),
-            path: String::from("anyone/pepe.jpg")
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -320,6 +356,7 @@ mod tests {
                 String::from("bob"),
                 String::from("charlie"),
             ],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -333,6 +370,7 @@ mod tests {
                 String::from("bob"),
                 String::from("charlie"),
             ],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -387,7 +425,7 @@ mod tests {
             },
         );
         let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
-        let test = File::new("anyone", "<content of test/ folder>");
+        let test = File::new_folder("anyone", "<content of test/ folder>", &mock_env("anyone", &[]));
         assert_eq!(test, value.file);
         println!("permissions disallowed for anyone/test/:\n {:#?}", value.file);
 
@@ -402,7 +440,7 @@ mod tests {
 
         // Create File
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::Create {
+        let msg = HandleMsg::CreateFolder {
             contents: String::from("Rainbows"),
             path: String::from("anyone/test/")
         };
@@ -423,13 +461,19 @@ mod tests {
 
         // Change owner. At the moment, only anyone (the owner) can do this
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/test/"),
             message: String::from("anyone has given you ownership of anyone/test/"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/test/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // Now alice can query "anyone/test/" but anyone cannot.
         let query_res = query(
             &deps,
@@ -463,6 +507,7 @@ mod tests {
             path: String::from("anyone/test/"),
             message: String::from("alice has given you read access to [ anyone/test ]"),
             address_list: vec![String::from("anyone")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -481,13 +526,19 @@ mod tests {
 
         // alice can change owner back to anyone
         let env = mock_env("alice", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/test/"),
             message: String::from("alice has given you ownership of anyone/test/"),
             new_owner: String::from("anyone"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/test/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         let query_res = query(
             &deps,
             QueryMsg::GetContents {
@@ -627,6 +678,7 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let create_vk_msg = HandleMsg::CreateViewingKey {
             entropy: "supbro".to_string(),
+            name: None,
             padding: None,
         };
         let handle_response = handle(&mut deps, env, create_vk_msg).unwrap();
@@ -715,7 +767,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("pepe"),
-            path: String::from("anyone/pepe.jpg")
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -737,6 +790,7 @@ mod tests {
             path: String::from("anyone/pepe.jpg"),
             message: String::from("anyone has given you read access to [ anyone/pepe.jpg ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -746,6 +800,7 @@ mod tests {
             path: String::from("anyone/phrog1.png"),
             message: String::from("anyone has given you read access to [ anyone/phrog1.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -755,6 +810,7 @@ mod tests {
             path: String::from("anyone/phrog2.png"),
             message: String::from("anyone has given you read access to [ anyone/phrog2.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -803,6 +859,7 @@ mod tests {
             path: String::from("anyone/phrog3.png"),
             message: String::from("anyone has given you read access to [ anyone/phrog3.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -818,13 +875,19 @@ mod tests {
         // Change Owner
 
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/phrog3.png"),
             message: String::from("anyone has given you ownership of anyone/phrog3.png"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/phrog3.png"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // Query Messages for alice
         let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("alice".to_string()), key: vk2.to_string() },).unwrap();
         let value: MessageResponse = from_binary(&query_res).unwrap();
@@ -870,7 +933,8 @@ mod tests {
         let env = mock_env("anyone", &[]);
         let msg = HandleMsg::Create {
             contents: String::from("pepe"),
-            path: String::from("anyone/pepe.jpg")
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -892,6 +956,7 @@ mod tests {
             path: String::from("anyone/pepe.jpg"),
             message: String::from("anyone has given you write access to [ anyone/pepe.jpg ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -901,6 +966,7 @@ mod tests {
             path: String::from("anyone/phrog1.png"),
             message: String::from("anyone has given you write access to [ anyone/phrog1.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -910,6 +976,7 @@ mod tests {
             path: String::from("anyone/phrog2.png"),
             message: String::from("anyone has given you write access to [ anyone/phrog2.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -958,6 +1025,7 @@ mod tests {
             path: String::from("anyone/phrog3.png"),
             message: String::from("anyone has given you write access to [ anyone/phrog3.png ]"),
             address_list: vec![String::from("alice"), String::from("bob")],
+            expires: None,
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
@@ -973,13 +1041,19 @@ mod tests {
         // Change Owner
 
         let env = mock_env("anyone", &[]);
-        let msg = HandleMsg::ChangeOwner {
+        let msg = HandleMsg::ProposeOwner {
             path: String::from("anyone/phrog3.png"),
             message: String::from("anyone has given you ownership of anyone/phrog3.png"),
             new_owner: String::from("alice"),
         };
         let _res = handle(&mut deps, env, msg).unwrap();
 
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/phrog3.png"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
         // Query Messages for alice
         let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("alice".to_string()), key: vk2.to_string() },).unwrap();
         let value: MessageResponse = from_binary(&query_res).unwrap();
@@ -1014,4 +1088,3319 @@ mod tests {
 
     }
 
+    #[test]
+    fn folder_contents_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // Root folder lists the sub folders made by InitAddress
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/movies/"), String::from("anyone/memes/"), String::from("anyone/work/")]);
+        assert!(value.files.is_empty());
+
+        // Create a sub folder and 2 files inside of anyone/memes/
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![
+                String::from("pepe"),
+                String::from("sad pepe"),
+            ],
+            path_list: vec![
+                String::from("anyone/memes/pepe.jpg"),
+                String::from("anyone/memes/sad.jpg"),
+            ]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/memes/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/memes/ contents:\n {:#?}", value);
+        assert_eq!(value.parent, String::from("anyone/memes/"));
+        assert_eq!(value.folders, vec![String::from("anyone/memes/old/")]);
+        assert_eq!(value.files, vec![String::from("anyone/memes/pepe.jpg"), String::from("anyone/memes/sad.jpg")]);
+
+        // alice can't list anyone/memes/ because she can't read it
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/memes/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Remove sad.jpg and move pepe.jpg into anyone/movies/
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Remove {
+            path: String::from("anyone/memes/sad.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/memes/pepe.jpg"),
+            new_path: String::from("anyone/movies/pepe.jpg"),
+            drop_acl: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/memes/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/memes/old/")]);
+        assert!(value.files.is_empty());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/movies/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![String::from("anyone/movies/pepe.jpg")]);
+
+        // pepe.jpg stops inheriting the permissions of anyone/movies/
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::SetInheritance { path: String::from("anyone/movies/pepe.jpg"), inherit: false };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Share anyone/movies/ with alice. She can list the folder, but pepe.jpg is left out since she can't read it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/movies/"),
+            message: String::from("anyone has given you read access to [ anyone/movies/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/movies/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.files.is_empty());
+    }
+
+    #[test]
+    fn folder_tree_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // Build anyone/work/a/b/ with a file on every level
+        for (contents, path) in vec![
+            ("<content inside a/>", "anyone/work/a/"),
+            ("<content inside b/>", "anyone/work/a/b/"),
+        ] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::CreateFolder {
+                contents: String::from(contents),
+                path: String::from(path),
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![
+                String::from("report"),
+                String::from("notes"),
+                String::from("draft"),
+            ],
+            path_list: vec![
+                String::from("anyone/work/report.txt"),
+                String::from("anyone/work/a/notes.txt"),
+                String::from("anyone/work/a/b/draft.txt"),
+            ]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Whole subtree with the default limits
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                max_depth: None,
+                max_entries: None,
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/work/ tree:\n {:#?}", value);
+        assert_eq!(value.folders, vec![String::from("anyone/work/a/"), String::from("anyone/work/a/b/")]);
+        assert_eq!(value.files, vec![
+            String::from("anyone/work/report.txt"),
+            String::from("anyone/work/a/notes.txt"),
+            String::from("anyone/work/a/b/draft.txt"),
+        ]);
+
+        // Depth 1 only returns the direct children
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                max_depth: Some(1),
+                max_entries: None,
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/work/a/")]);
+        assert_eq!(value.files, vec![String::from("anyone/work/report.txt")]);
+
+        // The entry limit cuts the walk short
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                max_depth: None,
+                max_entries: Some(3),
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders.len() + value.files.len(), 3);
+
+        // Keep everything but anyone/work/a/ from inheriting what is shared above it
+        for path in vec![String::from("anyone/work/report.txt"), String::from("anyone/work/a/notes.txt"), String::from("anyone/work/a/b/")] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::SetInheritance { path, inherit: false };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        // alice can read anyone/work/ and anyone/work/a/, but nothing else. Everything under anyone/work/a/b/ is left out
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/"),
+            message: String::from("anyone has given you read access to [ anyone/work/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/a/"),
+            message: String::from("anyone has given you read access to [ anyone/work/a/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                max_depth: None,
+                max_entries: None,
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/work/a/")]);
+        assert!(value.files.is_empty());
+
+        // alice is also given anyone/work/a/notes.txt directly, so the full tree shows it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/a/notes.txt"),
+            message: String::from("anyone has given you read access to [ anyone/work/a/notes.txt ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                max_depth: None,
+                max_entries: None,
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![String::from("anyone/work/a/notes.txt")]);
+
+        // Entries alice can't read still use up the limit: anyone/work/a/ and anyone/work/report.txt take both,
+        // so the walk stops before it reaches notes.txt
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/work/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                max_depth: None,
+                max_entries: Some(2),
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/work/a/")]);
+        assert!(value.files.is_empty());
+    }
+
+    #[test]
+    fn move_folder_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let _vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // Fill anyone/movies/ with a sub folder and some files
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/movies/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![
+                String::from("shrek"),
+                String::from("metropolis"),
+            ],
+            path_list: vec![
+                String::from("anyone/movies/shrek.mp4"),
+                String::from("anyone/movies/old/metropolis.mp4"),
+            ]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // alice can't move anyone's folder
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/"),
+            new_path: String::from("anyone/films/"),
+            drop_acl: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Moving onto a folder that already exists fails
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/"),
+            new_path: String::from("anyone/memes/"),
+            drop_acl: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+        println!("Can't move onto an existing folder:\n{:#?}", res);
+
+        // A folder can't be moved inside of itself
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/"),
+            new_path: String::from("anyone/movies/old/movies/"),
+            drop_acl: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Like a remove, a move takes MANAGE on everything inside the folder. With metropolis.mp4 given to alice, anyone can't move it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/movies/old/metropolis.mp4"),
+            message: String::from("anyone has given you ownership of anyone/movies/old/metropolis.mp4"),
+            new_owner: String::from("alice"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/movies/old/metropolis.mp4"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/"),
+            new_path: String::from("anyone/films/"),
+            drop_acl: None,
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "unauthorized");
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/movies/shrek.mp4"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        // alice hands it back
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/movies/old/metropolis.mp4"),
+            message: String::from("alice has given you ownership of anyone/movies/old/metropolis.mp4"),
+            new_owner: String::from("anyone"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/movies/old/metropolis.mp4"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Move anyone/movies/ to anyone/films/
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/"),
+            new_path: String::from("anyone/films/"),
+            drop_acl: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Every descendant now lives under anyone/films/
+        for path in vec!["anyone/films/", "anyone/films/old/", "anyone/films/shrek.mp4", "anyone/films/old/metropolis.mp4"] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from(path),
+                    behalf: HumanAddr("anyone".to_string()),
+                    key: vk.to_string(),
+                },
+            );
+            assert!(query_res.is_ok());
+        }
+        for path in vec!["anyone/movies/", "anyone/movies/old/", "anyone/movies/shrek.mp4", "anyone/movies/old/metropolis.mp4"] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from(path),
+                    behalf: HumanAddr("anyone".to_string()),
+                    key: vk.to_string(),
+                },
+            );
+            assert!(query_res.is_err());
+        }
+
+        // Child indexes follow the move
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/memes/"), String::from("anyone/work/"), String::from("anyone/films/")]);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderTree {
+                path: String::from("anyone/films/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                max_depth: None,
+                max_entries: None,
+            },
+        );
+        let value: BigTreeResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/films/ tree:\n {:#?}", value);
+        assert_eq!(value.folders, vec![String::from("anyone/films/old/")]);
+        assert_eq!(value.files, vec![String::from("anyone/films/shrek.mp4"), String::from("anyone/films/old/metropolis.mp4")]);
+    }
+
+    #[test]
+    fn remove_folder_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let _vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // Fill anyone/work/ with a sub folder and some files
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/work/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![
+                String::from("report"),
+                String::from("notes"),
+            ],
+            path_list: vec![
+                String::from("anyone/work/report.txt"),
+                String::from("anyone/work/old/notes.txt"),
+            ]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Remove refuses to orphan the children of a folder
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Remove {
+            path: String::from("anyone/work/"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // So does RemoveFolder without recursive
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveFolder {
+            path: String::from("anyone/work/"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+        println!("Non recursive remove of a non empty folder fails:\n{:#?}", res);
+
+        // Give report.txt to alice. anyone no longer owns everything in anyone/work/, so the recursive remove fails as a whole
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/work/report.txt"),
+            message: String::from("anyone has given you ownership of anyone/work/report.txt"),
+            new_owner: String::from("alice"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/work/report.txt"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveFolder {
+            path: String::from("anyone/work/"),
+            recursive: true,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/old/notes.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        // Once alice removes her file, anyone can remove the whole subtree
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Remove {
+            path: String::from("anyone/work/report.txt"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveFolder {
+            path: String::from("anyone/work/"),
+            recursive: true,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for path in vec!["anyone/work/", "anyone/work/old/", "anyone/work/old/notes.txt"] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from(path),
+                    behalf: HumanAddr("anyone".to_string()),
+                    key: vk.to_string(),
+                },
+            );
+            assert!(query_res.is_err());
+        }
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/movies/"), String::from("anyone/memes/")]);
+
+        // An empty folder can be removed without recursive
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveFolder {
+            path: String::from("anyone/memes/"),
+            recursive: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // The root folder can't be removed
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveFolder {
+            path: String::from("anyone/"),
+            recursive: true,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn move_keeps_permissions_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![String::from("pepe"), String::from("phrog")],
+            path_list: vec![String::from("anyone/memes/pepe.jpg"), String::from("anyone/memes/phrog.jpg")]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Share both files with alice
+        for path in vec!["anyone/memes/pepe.jpg", "anyone/memes/phrog.jpg"] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::AllowRead {
+                path: String::from(path),
+                message: format!("anyone has given you read access to [ {} ]", path),
+                address_list: vec![String::from("alice")],
+                expires: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        // A plain move keeps the whole record, so alice can still read the file at its new path
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/memes/pepe.jpg"),
+            new_path: String::from("anyone/movies/pepe.jpg"),
+            drop_acl: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/movies/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        let mut expected = File::new("anyone", "pepe", &mock_env("anyone", &[]));
+        expected.allow_read(String::from("alice"), None);
+        assert_eq!(value.file, expected);
+
+        // Moving with drop_acl makes the file private to its owner again
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/memes/phrog.jpg"),
+            new_path: String::from("anyone/movies/phrog.jpg"),
+            drop_acl: Some(true),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/movies/phrog.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/movies/phrog.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file, File::new("anyone", "phrog", &mock_env("anyone", &[])));
+
+        // Moving onto an existing file fails instead of overwriting it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/movies/phrog.jpg"),
+            new_path: String::from("anyone/movies/pepe.jpg"),
+            drop_acl: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn copy_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![
+                String::from("pepe"),
+                String::from("phrog"),
+                String::from("old pepe"),
+            ],
+            path_list: vec![
+                String::from("anyone/memes/pepe.jpg"),
+                String::from("anyone/memes/phrog.jpg"),
+                String::from("anyone/memes/old/pepe.jpg"),
+            ]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // anyone copies pepe.jpg within their own root. The original stays where it was
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/pepe.jpg"),
+            dst: String::from("anyone/movies/pepe.jpg"),
+            recursive: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for path in vec!["anyone/memes/pepe.jpg", "anyone/movies/pepe.jpg"] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from(path),
+                    behalf: HumanAddr("anyone".to_string()),
+                    key: vk.to_string(),
+                },
+            );
+            let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+            assert_eq!(value.file, File::new("anyone", "pepe", &mock_env("anyone", &[])));
+        }
+
+        // A file can be copied next to itself under a longer name, but not onto itself
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/pepe.jpg"),
+            dst: String::from("anyone/memes/pepe.jpg.bak"),
+            recursive: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/pepe.jpg.bak"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.get_contents(), "pepe");
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Remove { path: String::from("anyone/memes/pepe.jpg.bak") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/pepe.jpg"),
+            dst: String::from("anyone/memes/pepe.jpg"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Copying onto an existing file fails
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/phrog.jpg"),
+            dst: String::from("anyone/movies/pepe.jpg"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // alice can't copy phrog.jpg into her own root because she can't read it
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/phrog.jpg"),
+            dst: String::from("alice/memes/phrog.jpg"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Share pepe.jpg with alice and let her copy it into her own root. She owns the copy
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/memes/pepe.jpg"),
+            message: String::from("anyone has given you read access to [ anyone/memes/pepe.jpg ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/pepe.jpg"),
+            dst: String::from("alice/memes/pepe.jpg"),
+            recursive: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/memes/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file, File::new("alice", "pepe", &mock_env("alice", &[])));
+
+        // alice can't copy into anyone's root without write permission
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("alice/memes/pepe.jpg"),
+            dst: String::from("anyone/work/pepe.jpg"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // phrog.jpg and old/ stop inheriting the permissions of anyone/memes/
+        for path in vec![String::from("anyone/memes/phrog.jpg"), String::from("anyone/memes/old/")] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::SetInheritance { path, inherit: false };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        // Folders need recursive set
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/memes/"),
+            message: String::from("anyone has given you read access to [ anyone/memes/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/"),
+            dst: String::from("alice/work/memes/"),
+            recursive: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // A recursive copy only takes what alice can read: pepe.jpg, but not phrog.jpg or old/
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::CopyMulti {
+            src_list: vec![String::from("anyone/memes/")],
+            dst_list: vec![String::from("alice/work/memes/")],
+            recursive: true,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("alice/work/memes/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("alice/work/memes/ contents:\n {:#?}", value);
+        assert!(value.folders.is_empty());
+        assert_eq!(value.files, vec![String::from("alice/work/memes/pepe.jpg")]);
+
+        // anyone copies the whole folder, old/ and everything inside of it included
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/"),
+            dst: String::from("anyone/work/memes/"),
+            recursive: true,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/memes/old/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file, File::new("anyone", "old pepe", &mock_env("anyone", &[])));
+
+        // A folder can't be copied inside of itself
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/"),
+            dst: String::from("anyone/memes/old/memes/"),
+            recursive: true,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn path_validation_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+
+        // Malformed paths are rejected before anything is written
+        let long_name = "a".repeat(200);
+        let bad_paths = vec![
+            String::from(""),
+            String::from("anyone//pepe.jpg"),
+            String::from("anyone/../alice/pepe.jpg"),
+            String::from("anyone/./pepe.jpg"),
+            String::from("anyone/memes/pepe.jpg "),
+            String::from("anyone/ memes/pepe.jpg"),
+            String::from("/anyone/pepe.jpg"),
+            String::from("pepe.jpg"),
+            format!("anyone/{}", long_name),
+            format!("anyone/{}pepe.jpg", "a/".repeat(20)),
+        ];
+        for path in bad_paths {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::Create {
+                contents: String::from("pepe"),
+                path: path.to_string(),
+                content_type: None,
+            };
+            let res = handle(&mut deps, env, msg);
+            println!("Create at '{}': {:?}", path, res);
+            assert!(res.is_err());
+        }
+
+        // Every path in a multi message is checked, including the destination of a move
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/memes/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::MoveMulti {
+            old_path_list: vec![String::from("anyone/memes/pepe.jpg")],
+            new_path_list: vec![String::from("anyone/movies//pepe.jpg")],
+            drop_acl: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/memes/../pepe.jpg"),
+            message: String::from("anyone has given you read access"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file, File::new("anyone", "pepe", &mock_env("anyone", &[])));
+
+        // InitAddress checks the sub folders it is asked to create
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::InitAddress {
+            contents_list: vec![String::from("root contents"), String::from("sub_folder_1 contents")],
+            path_list: vec![String::from("../anyone/")],
+            entropy: String::from("Entropygoeshereboi"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn node_kind_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+
+        // Create only makes files
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+            content_type: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // CreateFolder only makes folders
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("pepe"),
+            path: String::from("anyone/memes/pepe.jpg"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/old/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.file.is_folder());
+        assert_eq!(value.file, File::new_folder("anyone", "<content inside old/>", &mock_env("anyone", &[])));
+
+        // A folder that already exists can't be created again
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<new content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // A file can't have children
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/memes/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("child"),
+            path: String::from("anyone/memes/pepe.jpg/child"),
+            content_type: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Nothing can be moved or copied under a file either
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/old/"),
+            dst: String::from("anyone/memes/pepe.jpg/old/"),
+            recursive: true,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // The folder queries tell folders and files apart by their record
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("anyone/memes/"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("anyone/memes/old/")]);
+        assert_eq!(value.files, vec![String::from("anyone/memes/pepe.jpg")]);
+    }
+
+    #[test]
+    fn stat_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // Create pepe.jpg a few blocks after init
+        let mut env = mock_env("anyone", &[]);
+        env.block.height += 10;
+        env.block.time += 60;
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/memes/pepe.jpg"),
+            content_type: Some(String::from("image/jpeg")),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::Stat {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: StatResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/memes/pepe.jpg metadata:\n {:#?}", value);
+        assert_eq!(value.metadata.size, 4);
+        assert_eq!(value.metadata.content_type, Some(String::from("image/jpeg")));
+        assert_eq!(value.metadata.checksum, hex::encode(sha_256(b"pepe")));
+        assert_eq!(value.metadata.created_at, BlockTime { height: env.block.height, time: env.block.time });
+        assert_eq!(value.metadata.modified_at, value.metadata.created_at);
+
+        // alice can't see the metadata of a file she can't read
+        let query_res = query(
+            &deps,
+            QueryMsg::Stat {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // A copy is a new file, but it keeps its content type
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Copy {
+            src: String::from("anyone/memes/pepe.jpg"),
+            dst: String::from("anyone/movies/pepe.jpg"),
+            recursive: false,
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::Stat {
+                path: String::from("anyone/movies/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: StatResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.metadata.content_type, Some(String::from("image/jpeg")));
+        assert_eq!(value.metadata.created_at, BlockTime::from_env(&env));
+    }
+
+    #[test]
+    fn versions_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+
+        // anyone writes notes.txt 4 times, giving alice write access after the first one
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("draft 1"),
+            path: String::from("anyone/work/notes.txt"),
+            content_type: Some(String::from("text/plain")),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowWrite {
+            path: String::from("anyone/work/notes.txt"),
+            message: String::from("anyone has given you write access to [ anyone/work/notes.txt ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for (author, contents) in vec![("alice", "draft 2"), ("anyone", "draft 3"), ("alice", "draft 4")] {
+            let mut env = mock_env(author, &[]);
+            env.block.height += 1;
+            let msg = HandleMsg::Create {
+                contents: String::from(contents),
+                path: String::from("anyone/work/notes.txt"),
+                content_type: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersions {
+                path: String::from("anyone/work/notes.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: VersionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/work/notes.txt versions:\n {:#?}", value);
+        assert_eq!(value.current.version, 4);
+        assert_eq!(value.current.modified_by, String::from("alice"));
+        assert_eq!(value.current.content_type, Some(String::from("text/plain")));
+        assert_eq!(value.versions.iter().map(|v| v.version).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        assert_eq!(value.versions[1].modified_by, String::from("alice"));
+
+        // Overwriting keeps the owner and permissions
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/notes.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.get_contents(), "draft 4");
+        assert!(value.file.can_write(String::from("alice"), &[], &BlockTime::from_env(&mock_env("alice", &[]))));
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersionContents {
+                path: String::from("anyone/work/notes.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                version: 2,
+            },
+        );
+        let value: VersionContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.version.contents, String::from("draft 2"));
+
+        // Revert to version 1. The revert is a write of its own, so draft 4 is kept as version 4
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RevertFile {
+            path: String::from("anyone/work/notes.txt"),
+            version: 1,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersionContents {
+                path: String::from("anyone/work/notes.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                version: 5,
+            },
+        );
+        let value: VersionContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.version.contents, String::from("draft 1"));
+
+        // Only keep 2 versions of notes.txt. Older ones are dropped right away
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::SetRetention {
+            path: Some(String::from("anyone/work/notes.txt")),
+            versions: Some(2),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::SetRetention {
+            path: Some(String::from("anyone/work/notes.txt")),
+            versions: Some(2),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersions {
+                path: String::from("anyone/work/notes.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: VersionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.versions.iter().map(|v| v.version).collect::<Vec<u32>>(), vec![3, 4]);
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RevertFile {
+            path: String::from("anyone/work/notes.txt"),
+            version: 1,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // The history moves along with the file
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/work/notes.txt"),
+            new_path: String::from("anyone/memes/notes.txt"),
+            drop_acl: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersions {
+                path: String::from("anyone/memes/notes.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: VersionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.versions.len(), 2);
+
+        // A wallet wide retention of 0 keeps no history for anyone's other files
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::SetRetention {
+            path: None,
+            versions: Some(0),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for contents in vec!["pepe", "sad pepe"] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::Create {
+                contents: String::from(contents),
+                path: String::from("anyone/memes/pepe.jpg"),
+                content_type: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersions {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: VersionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.current.version, 2);
+        assert!(value.versions.is_empty());
+    }
+
+    #[test]
+    fn public_contents_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/memes/pepe.jpg"),
+            content_type: Some(String::from("image/jpeg")),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Private files can't be read without a viewing key
+        let query_res = query(&deps, QueryMsg::GetPublicContents { path: String::from("anyone/memes/pepe.jpg") });
+        assert!(query_res.is_err());
+
+        // Only the owner can make a file public
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::MakePublic { path: String::from("anyone/memes/pepe.jpg") };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::MakePublic { path: String::from("anyone/memes/pepe.jpg") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::GetPublicContents { path: String::from("anyone/memes/pepe.jpg") });
+        let value: PublicContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("public contents of anyone/memes/pepe.jpg:\n {:#?}", value);
+        assert_eq!(value.contents, String::from("pepe"));
+        assert_eq!(value.content_type, Some(String::from("image/jpeg")));
+
+        // Public files can be read by every address with a viewing key too
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::MakePrivate { path: String::from("anyone/memes/pepe.jpg") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::GetPublicContents { path: String::from("anyone/memes/pepe.jpg") });
+        assert!(query_res.is_err());
+
+        // Missing files look the same as private ones
+        let query_res = query(&deps, QueryMsg::GetPublicContents { path: String::from("anyone/memes/nothing.jpg") });
+        assert!(query_res.is_err());
+    }
+
+    #[test]
+    fn inherited_permissions_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let _vk3 = init_for_test(&mut deps, String::from("bob"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder {
+            contents: String::from("<content inside old/>"),
+            path: String::from("anyone/memes/old/"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![String::from("pepe"), String::from("old pepe")],
+            path_list: vec![String::from("anyone/memes/pepe.jpg"), String::from("anyone/memes/old/pepe.jpg")]
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Sharing anyone/memes/ with alice lets her read everything inside of it, however deep
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/memes/"),
+            message: String::from("anyone has given you read access to [ anyone/memes/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for path in vec!["anyone/memes/pepe.jpg", "anyone/memes/old/pepe.jpg"] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from(path),
+                    behalf: HumanAddr("alice".to_string()),
+                    key: vk2.to_string(),
+                },
+            );
+            assert!(query_res.is_ok());
+        }
+
+        // Read access alone doesn't let alice write inside of the folder
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("alice's pepe"),
+            path: String::from("anyone/memes/old/alice.jpg"),
+            content_type: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // bob gets write access to anyone/memes/, so he can create files in old/ and overwrite the ones already there
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowWrite {
+            path: String::from("anyone/memes/"),
+            message: String::from("anyone has given you write access to [ anyone/memes/ ]"),
+            address_list: vec![String::from("bob")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("bob's pepe"),
+            path: String::from("anyone/memes/old/bob.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("new pepe"),
+            path: String::from("anyone/memes/old/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Only the owner can stop a file from inheriting
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::SetInheritance {
+            path: String::from("anyone/memes/old/"),
+            inherit: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Once old/ stops inheriting, neither old/ nor anything inside of it can be read by alice
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::SetInheritance {
+            path: String::from("anyone/memes/old/"),
+            inherit: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/old/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/memes/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+    }
+
+    #[test]
+    fn expiring_permissions_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let _vk3 = init_for_test(&mut deps, String::from("bob"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/work/contract.txt"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // alice can read contract.txt for 10 blocks, bob can write to it for 100 seconds
+        let env = mock_env("anyone", &[]);
+        let height = env.block.height;
+        let time = env.block.time;
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/contract.txt"),
+            message: String::from("anyone has given you read access to [ anyone/work/contract.txt ]"),
+            address_list: vec![String::from("alice")],
+            expires: Some(Expiration::AtHeight(height + 10)),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowWrite {
+            path: String::from("anyone/work/contract.txt"),
+            message: String::from("anyone has given you write access to [ anyone/work/contract.txt ]"),
+            address_list: vec![String::from("bob")],
+            expires: Some(Expiration::AtTime(time + 100)),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        // 20 blocks and 60 seconds later alice's grant has run out, but bob's hasn't
+        let mut env = mock_env("bob", &[]);
+        env.block.height = height + 20;
+        env.block.time = time + 60;
+        let msg = HandleMsg::Create {
+            contents: String::from("bob's edit"),
+            path: String::from("anyone/work/contract.txt"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Writing the file dropped alice's expired grant
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("anyone/work/contract.txt after alice's grant ran out:\n {:#?}", value.file);
+        assert_eq!(value.file.get_contents(), "bob's edit");
+        assert!(value.file.addresses(&PermType::READ).is_empty());
+        assert_eq!(value.file.addresses(&PermType::WRITE), vec![String::from("bob")]);
+
+        // Once bob's time is up as well, he can't write anymore
+        let mut env = mock_env("bob", &[]);
+        env.block.height = height + 30;
+        env.block.time = time + 100;
+        let msg = HandleMsg::Create {
+            contents: String::from("bob's second edit"),
+            path: String::from("anyone/work/contract.txt"),
+            content_type: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Granting again replaces the old grant, so anyone can give alice access without an end
+        let mut env = mock_env("anyone", &[]);
+        env.block.height = height + 30;
+        env.block.time = time + 100;
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/contract.txt"),
+            message: String::from("anyone has given you read access to [ anyone/work/contract.txt ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+    }
+
+    #[test]
+    fn permission_levels_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let _vk2 = init_for_test(&mut deps, String::from("alice"));
+        let vk3 = init_for_test(&mut deps, String::from("bob"));
+        let _vk4 = init_for_test(&mut deps, String::from("charlie"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // alice gets WRITE, which isn't enough to share the file
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Grant {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given you write access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("alice")],
+            level: PermType::WRITE,
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("alice has given you read access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("bob")],
+            expires: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // With SHARE alice can give bob access, but only up to her own level
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Grant {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given you share access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("alice")],
+            level: PermType::SHARE,
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Grant {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("alice has given you manage access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("bob")],
+            level: PermType::MANAGE,
+            expires: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Grant {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("alice has given you comment access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("bob")],
+            level: PermType::COMMENT,
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // COMMENT includes READ but not WRITE
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("bob's pepe"),
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // With COMMENT bob can send anyone a comment on the file, charlie without a grant can't
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::Comment {
+            path: String::from("anyone/pepe.jpg"),
+            contents: String::from("nice frog"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("charlie", &[]);
+        let msg = HandleMsg::Comment {
+            path: String::from("anyone/pepe.jpg"),
+            contents: String::from("ugly frog"),
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "unauthorized");
+
+        let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("anyone".to_string()), key: vk.to_string() },).unwrap();
+        let value: MessageResponse = from_binary(&query_res).unwrap();
+        println!("anyone's messages --> {:#?}", value.messages);
+        assert_eq!(value.messages.last().unwrap().get_contents(), "Comment on [ anyone/pepe.jpg ]: nice frog");
+
+        // SHARE doesn't cover resetting lists, removing the file or changing its owner
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::ResetRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone/pepe.jpg has been reset"),
+            notify: false,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Remove {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("alice has given you anyone/pepe.jpg"),
+            new_owner: String::from("alice"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // charlie is given MANAGE and revokes everything bob and alice hold
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Grant {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given you manage access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("charlie")],
+            level: PermType::MANAGE,
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Revoke {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("alice has taken away your access to [ anyone/pepe.jpg ]"),
+            notify: false,
+            address_list: vec![String::from("charlie")],
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        let env = mock_env("charlie", &[]);
+        let msg = HandleMsg::Revoke {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("charlie has taken away your access to [ anyone/pepe.jpg ]"),
+            notify: false,
+            address_list: vec![String::from("alice"), String::from("bob")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Only charlie's grant is left, and with MANAGE he can remove the file
+        let env = mock_env("charlie", &[]);
+        let msg = HandleMsg::Remove {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+        println!("charlie removed anyone/pepe.jpg");
+
+        // Resetting the write list takes away every level that includes WRITE, and leaves the ones below it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("doge"),
+            path: String::from("anyone/doge.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for (address, level) in vec![("alice", PermType::SHARE), ("bob", PermType::COMMENT), ("charlie", PermType::MANAGE)] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::Grant {
+                path: String::from("anyone/doge.jpg"),
+                message: String::from("anyone has given you access to [ anyone/doge.jpg ]"),
+                address_list: vec![String::from(address)],
+                level,
+                expires: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ResetWrite {
+            path: String::from("anyone/doge.jpg"),
+            message: String::from("anyone/doge.jpg has been reset"),
+            notify: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/doge.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.addresses_from(&PermType::READ), vec![String::from("bob")]);
+
+        // And resetting the read list takes away everything
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ResetRead {
+            path: String::from("anyone/doge.jpg"),
+            message: String::from("anyone/doge.jpg has been reset"),
+            notify: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/doge.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+    }
+
+    #[test]
+    fn ownership_transfer_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let _vk2 = init_for_test(&mut deps, String::from("alice"));
+        let _vk3 = init_for_test(&mut deps, String::from("bob"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // anyone offers pepe.jpg to alice. Until she accepts, anyone still owns it
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone wants to give you ownership of anyone/pepe.jpg"),
+            new_owner: String::from("alice"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("pepe.jpg with a pending owner:\n {:#?}", value.file);
+        assert_eq!(value.file.get_owner(), "anyone");
+        assert_eq!(value.file.pending_owner(), Some(&String::from("alice")));
+
+        // bob wasn't offered the file, so he can't accept it
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // alice declines, after which there is nothing left to accept
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::DeclineOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // anyone offers it to bob by mistake and cancels before bob accepts
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone wants to give you ownership of anyone/pepe.jpg"),
+            new_owner: String::from("bob"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CancelOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // The second offer to alice goes through once she accepts
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone wants to give you ownership of anyone/pepe.jpg"),
+            new_owner: String::from("alice"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AcceptOwnership {
+            path: String::from("anyone/pepe.jpg"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // anyone no longer owns the file, so they can't cancel or make offers anymore
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone wants to give you ownership of anyone/pepe.jpg"),
+            new_owner: String::from("bob"),
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn groups_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let vk3 = init_for_test(&mut deps, String::from("bob"));
+        let vk4 = init_for_test(&mut deps, String::from("charlie"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/pepe.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // anyone puts alice and bob in a group and gives the group read access
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateGroup {
+            name: String::from("team"),
+            members: vec![String::from("alice"), String::from("bob")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given your team read access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("group:team")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Granting a group that doesn't exist fails
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given your team read access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("group:nobody")],
+            expires: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        for (address, key) in vec![("alice", &vk2), ("bob", &vk3)] {
+            let query_res = query(
+                &deps,
+                QueryMsg::GetContents {
+                    path: String::from("anyone/pepe.jpg"),
+                    behalf: HumanAddr(address.to_string()),
+                    key: key.to_string(),
+                },
+            );
+            assert!(query_res.is_ok());
+        }
+
+        // Every member got the message
+        let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("bob".to_string()), key: vk3.to_string() },).unwrap();
+        let value: MessageResponse = from_binary(&query_res).unwrap();
+        println!("Bob's messages --> {:#?}", value.messages);
+        assert_eq!(value.messages.len(), 2);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("charlie".to_string()),
+                key: vk4.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Membership changes apply to the grant straight away
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AddGroupMembers {
+            name: String::from("team"),
+            members: vec![String::from("charlie")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::RemoveGroupMembers {
+            name: String::from("team"),
+            members: vec![String::from("bob")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("charlie".to_string()),
+                key: vk4.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // alice can't change a group that belongs to anyone
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AddGroupMembers {
+            name: String::from("team"),
+            members: vec![String::from("bob")],
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err());
+
+        // Once the group is deleted, its grant doesn't give anyone access anymore
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::DeleteGroup {
+            name: String::from("team"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // A group created again under the same name is a new group, and the old grant doesn't reach its members
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateGroup {
+            name: String::from("team"),
+            members: vec![String::from("alice")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given your team read access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("group:team")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_ok());
+
+        // Only groups of the file's owner or of the sender can be granted, not someone else's
+        let env = mock_env("charlie", &[]);
+        let msg = HandleMsg::CreateGroup {
+            name: String::from("crew"),
+            members: vec![String::from("bob")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has given charlie's crew read access to [ anyone/pepe.jpg ]"),
+            address_list: vec![String::from("group:charlie/crew")],
+            expires: None,
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "unauthorized");
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/pepe.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+    }
+
+    #[test]
+    fn shared_with_me_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let _vk3 = init_for_test(&mut deps, String::from("bob"));
+
+        for (contents, path) in vec![("pepe", "anyone/pepe.jpg"), ("doge", "anyone/doge.jpg"), ("kitty", "anyone/kitty.jpg")] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::Create {
+                contents: String::from(contents),
+                path: String::from(path),
+                content_type: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        // alice is given pepe.jpg and doge.jpg directly, and kitty.jpg through a group
+        for path in vec!["anyone/pepe.jpg", "anyone/doge.jpg"] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::AllowRead {
+                path: String::from(path),
+                message: format!("anyone has given you read access to [ {} ]", path),
+                address_list: vec![String::from("alice")],
+                expires: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateGroup {
+            name: String::from("team"),
+            members: vec![String::from("alice")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowWrite {
+            path: String::from("anyone/kitty.jpg"),
+            message: String::from("anyone has given your team write access to [ anyone/kitty.jpg ]"),
+            address_list: vec![String::from("group:team")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("Shared with alice:\n {:#?}", value.files);
+        assert_eq!(value.files, vec![
+            SharedFile { path: String::from("anyone/doge.jpg"), level: PermType::READ },
+            SharedFile { path: String::from("anyone/kitty.jpg"), level: PermType::WRITE },
+            SharedFile { path: String::from("anyone/pepe.jpg"), level: PermType::READ },
+        ]);
+
+        // Two at a time, picking up after the last path of the first page
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: Some(2),
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files.len(), 2);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: Some(value.files[1].path.to_string()),
+                limit: Some(2),
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![SharedFile { path: String::from("anyone/pepe.jpg"), level: PermType::READ }]);
+
+        // Disallowing, resetting and removing all take the file out of alice's list
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::DisallowRead {
+            path: String::from("anyone/pepe.jpg"),
+            message: String::from("anyone has taken away your read access to [ anyone/pepe.jpg ]"),
+            notify: false,
+            address_list: vec![String::from("alice")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ResetWrite {
+            path: String::from("anyone/kitty.jpg"),
+            message: String::from("anyone/kitty.jpg has been reset"),
+            notify: false,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![SharedFile { path: String::from("anyone/doge.jpg"), level: PermType::READ }]);
+
+        // Moving a shared folder takes its old path out of alice's list and puts the new one in
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/memes/"),
+            message: String::from("anyone has given you read access to [ anyone/memes/ ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Move {
+            old_path: String::from("anyone/memes/"),
+            new_path: String::from("anyone/work/memes/"),
+            drop_acl: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("Shared with alice after the move:\n {:#?}", value.files);
+        assert_eq!(value.files, vec![
+            SharedFile { path: String::from("anyone/doge.jpg"), level: PermType::READ },
+            SharedFile { path: String::from("anyone/work/memes/"), level: PermType::READ },
+        ]);
+
+        // The old path isn't left behind in the index either
+        let mut shared = bucket_load_shared(&deps.storage, &String::from("alice")).unwrap();
+        assert!(!shared.contains(&String::from("anyone/memes/")));
+
+        // Once anyone forgets their storage, nothing of theirs is shared with alice anymore
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ForgetMe {};
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.files.is_empty());
+    }
+
+    #[test]
+    fn permission_queries_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let vk = init_for_test(&mut deps, String::from("anyone"));
+        let vk2 = init_for_test(&mut deps, String::from("alice"));
+        let vk3 = init_for_test(&mut deps, String::from("bob"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: String::from("anyone/work/contract.txt"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // bob can read everything in work/, alice can write contract.txt
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("anyone/work/"),
+            message: String::from("anyone has given you read access to [ anyone/work/ ]"),
+            address_list: vec![String::from("bob")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::AllowWrite {
+            path: String::from("anyone/work/contract.txt"),
+            message: String::from("anyone has given you write access to [ anyone/work/contract.txt ]"),
+            address_list: vec![String::from("alice")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetPermissions {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: PermissionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("Permissions on anyone/work/contract.txt:\n {:#?}", value);
+        assert_eq!(value.owner, "anyone");
+        assert_eq!(value.permissions.len(), 1);
+        assert_eq!(value.permissions[0].address, "alice");
+        assert_eq!(value.permissions[0].permission_type, PermType::WRITE);
+        assert_eq!(value.inherited.len(), 1);
+        assert_eq!(value.inherited[0].path, "anyone/work/");
+        assert_eq!(value.inherited[0].permissions[0].address, "bob");
+
+        // Writing isn't enough to see who else has access
+        let query_res = query(
+            &deps,
+            QueryMsg::GetPermissions {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // bob checks his own access, which comes from work/
+        let query_res = query(
+            &deps,
+            QueryMsg::CheckAccess {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+                address: String::from("bob"),
+                level: PermType::READ,
+            },
+        );
+        let value: CheckAccessResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.allowed);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::CheckAccess {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+                address: String::from("bob"),
+                level: PermType::WRITE,
+            },
+        );
+        let value: CheckAccessResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(!value.allowed);
+
+        // A path that doesn't exist looks the same as one bob can't get into
+        let query_res = query(
+            &deps,
+            QueryMsg::CheckAccess {
+                path: String::from("anyone/work/missing.txt"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+                address: String::from("bob"),
+                level: PermType::READ,
+            },
+        );
+        let value: CheckAccessResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(!value.allowed);
+
+        // bob can't ask about alice, but the owner can
+        let query_res = query(
+            &deps,
+            QueryMsg::CheckAccess {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk3.to_string(),
+                address: String::from("alice"),
+                level: PermType::WRITE,
+            },
+        );
+        assert!(query_res.is_err());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::CheckAccess {
+                path: String::from("anyone/work/contract.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: vk.to_string(),
+                address: String::from("alice"),
+                level: PermType::WRITE,
+            },
+        );
+        let value: CheckAccessResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.allowed);
+    }
+
+    fn sign_permit(signing_key: &SigningKey, params: PermitParams) -> Permit {
+        let signature: Signature = signing_key.sign(&params.sign_bytes().unwrap());
+        Permit {
+            params,
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: String::from("tendermint/PubKeySecp256k1"),
+                    value: Binary(signing_key.verifying_key().to_bytes().to_vec()),
+                },
+                signature: Binary(signature.as_ref().to_vec()),
+            },
+        }
+    }
+
+    #[test]
+    fn permit_test() {
+        // bech32 addresses are longer than the 20 bytes the other tests get away with
+        let mut deps = mock_dependencies(45, &[]);
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let signer = pub_key_to_address(&signing_key.verifying_key().to_bytes()).unwrap();
+        let _vk = init_for_test(&mut deps, signer.to_string());
+
+        let path = format!("{}/pepe.jpg", signer);
+        let env = mock_env(signer.to_string(), &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("pepe"),
+            path: path.to_string(),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // The signer reads their file with a permit instead of a viewing key
+        let params = PermitParams {
+            allowed_tokens: vec![HumanAddr::from("cosmos2contract")],
+            permit_name: String::from("my dapp"),
+            chain_id: String::from("cosmos-testnet-14002"),
+            permissions: vec![Permission::Files],
+        };
+        let permit = sign_permit(&signing_key, params.clone());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit: permit.clone(),
+                query: QueryWithPermit::GetContents { path: path.to_string() },
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        println!("{} read with a permit:\n {:#?}", path, value.file);
+        assert_eq!(value.file.get_contents(), "pepe");
+
+        // The permit only covers files, not messages
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit: permit.clone(),
+                query: QueryWithPermit::GetMessages {},
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Changing the params after signing breaks the signature
+        let mut tampered = permit.clone();
+        tampered.params.permissions.push(Permission::Messages);
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit: tampered,
+                query: QueryWithPermit::GetMessages {},
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Permits for another contract or chain aren't accepted
+        let other_contract = sign_permit(&signing_key, PermitParams {
+            allowed_tokens: vec![HumanAddr::from("some_other_contract")],
+            ..params.clone()
+        });
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit: other_contract,
+                query: QueryWithPermit::GetContents { path: path.to_string() },
+            },
+        );
+        assert!(query_res.is_err());
+
+        let other_chain = sign_permit(&signing_key, PermitParams {
+            chain_id: String::from("secret-4"),
+            ..params.clone()
+        });
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit: other_chain,
+                query: QueryWithPermit::GetContents { path: path.to_string() },
+            },
+        );
+        assert!(query_res.is_err());
+
+        // Once revoked by name, the permit stops working
+        let env = mock_env(signer.to_string(), &[]);
+        let msg = HandleMsg::RevokePermit {
+            permit_name: String::from("my dapp"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit {
+                permit,
+                query: QueryWithPermit::GetContents { path: path.to_string() },
+            },
+        );
+        assert!(query_res.is_err());
+    }
+
+    #[test]
+    fn legacy_config_test() {
+        let mut deps = mock_dependencies(45, &[]);
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let signer = pub_key_to_address(&signing_key.verifying_key().to_bytes()).unwrap();
+
+        // The config the way contracts deployed before permits saved it
+        #[derive(Serialize, Deserialize)]
+        struct BaselineState { owner: HumanAddr, prng_seed: Vec<u8> }
+        let config = BaselineState { owner: HumanAddr::from("creator"), prng_seed: sha_256(b"seed").to_vec() };
+        save(&mut deps.storage, CONFIG_KEY, &config).unwrap();
+
+        let state: State = load(&deps.storage, CONFIG_KEY).unwrap();
+        assert_eq!(state.prng_seed, sha_256(b"seed").to_vec());
+
+        // Until the contract handles a message there's nothing to check permits against
+        let params = PermitParams {
+            allowed_tokens: vec![HumanAddr::from("cosmos2contract")],
+            permit_name: String::from("my dapp"),
+            chain_id: String::from("cosmos-testnet-14002"),
+            permissions: vec![Permission::Files],
+        };
+        let permit = sign_permit(&signing_key, params);
+        let path = format!("{}/", signer);
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit { permit: permit.clone(), query: QueryWithPermit::GetContents { path: path.to_string() } },
+        );
+        assert!(query_res.is_err());
+
+        // InitAddress still reads the prng seed, and from then on permits are checked against its env
+        let _vk = init_for_test(&mut deps, signer.to_string());
+
+        let query_res = query(
+            &deps,
+            QueryMsg::WithPermit { permit, query: QueryWithPermit::GetContents { path } },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert!(value.file.is_folder());
+    }
+
+    #[test]
+    fn legacy_node_test() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        // A node record the way it was saved while addresses were plain strings
+        #[derive(Serialize, Deserialize)]
+        struct BaselineNodeData { score: u32, secret_address: String }
+        let node = BaselineNodeData { score: 500, secret_address: String::from("alice") };
+        bucket(b"NODES", &mut deps.storage).save(b"127.0.0.1", &node).unwrap();
+
+        let node = load_node_data(&deps.storage, &deps.api, String::from("127.0.0.1")).unwrap();
+        assert_eq!(node.secret_address(), &deps.api.canonical_address(&HumanAddr::from("alice")).unwrap());
+
+        // Nodes added from now on are saved with the canonical address and read back the same way
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::InitNode { ip: String::from("127.0.0.2"), address: String::from("bob") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let node = load_node_data(&deps.storage, &deps.api, String::from("127.0.0.2")).unwrap();
+        assert_eq!(node.secret_address(), &deps.api.canonical_address(&HumanAddr::from("bob")).unwrap());
+
+        assert!(load_node_data(&deps.storage, &deps.api, String::from("127.0.0.3")).is_err());
+    }
+
+    #[test]
+    fn scoped_key_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("alice"));
+
+        for (contents, path) in vec![("sunset", "alice/movies/sunset.jpg"), ("payroll", "alice/work/payroll.txt")] {
+            let env = mock_env("alice", &[]);
+            let msg = HandleMsg::Create {
+                contents: String::from(contents),
+                path: String::from(path),
+                content_type: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        // alice gives a viewer app a key that can only read files under alice/movies/, for 100 blocks
+        let env = mock_env("alice", &[]);
+        let height = env.block.height;
+        let msg = HandleMsg::CreateScopedKey {
+            name: String::from("viewer"),
+            entropy: String::from("viewer entropy"),
+            path_prefix: Some(String::from("alice/movies/")),
+            permissions: vec![Permission::Files],
+            expires: Some(height + 100),
+        };
+        let handle_response = handle(&mut deps, env, msg).unwrap();
+        let viewer_key = match from_binary(&handle_response.data.unwrap()).unwrap() {
+            HandleAnswer::CreateScopedKey { key, .. } => key,
+            _ => panic!("Unexpected result from handle"),
+        };
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/movies/sunset.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: viewer_key.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.get_contents(), "sunset");
+
+        // Outside of alice/movies/, or for anything other than files, the key doesn't work
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/work/payroll.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: viewer_key.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("alice".to_string()), key: viewer_key.to_string() },);
+        assert!(query_res.is_err());
+
+        // The key stops working once its block has passed
+        let mut env = mock_env("alice", &[]);
+        env.block.height = height + 100;
+        let msg = HandleMsg::Create {
+            contents: String::from("sunrise"),
+            path: String::from("alice/movies/sunrise.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/movies/sunset.jpg"),
+                behalf: HumanAddr("alice".to_string()),
+                key: viewer_key.to_string(),
+            },
+        );
+        assert!(query_res.is_err());
+
+        // A second key without a prefix or expiry works until it is revoked
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::CreateScopedKey {
+            name: String::from("inbox"),
+            entropy: String::from("inbox entropy"),
+            path_prefix: None,
+            permissions: vec![Permission::Messages],
+            expires: None,
+        };
+        let handle_response = handle(&mut deps, env, msg).unwrap();
+        let inbox_key = match from_binary(&handle_response.data.unwrap()).unwrap() {
+            HandleAnswer::CreateScopedKey { key, .. } => key,
+            _ => panic!("Unexpected result from handle"),
+        };
+
+        let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("alice".to_string()), key: inbox_key.to_string() },);
+        assert!(query_res.is_ok());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::RevokeScopedKey {
+            name: String::from("inbox"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::GetMessages { behalf: HumanAddr("alice".to_string()), key: inbox_key.to_string() },);
+        assert!(query_res.is_err());
+    }
+
+    #[test]
+    fn multiple_viewing_keys_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let init_key = init_for_test(&mut deps, String::from("alice"));
+
+        // alice makes a key for her phone and chooses one herself for her desktop
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::CreateViewingKey {
+            entropy: String::from("phone entropy"),
+            name: Some(String::from("phone")),
+            padding: None,
+        };
+        let handle_response = handle(&mut deps, env, msg).unwrap();
+        let phone_key = match from_binary(&handle_response.data.unwrap()).unwrap() {
+            HandleAnswer::CreateViewingKey { key } => key,
+            _ => panic!("Unexpected result from handle"),
+        };
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::SetViewingKey {
+            key: String::from("desktop key"),
+            name: Some(String::from("desktop")),
+            padding: None,
+        };
+        let handle_response = handle(&mut deps, env, msg).unwrap();
+        match from_binary(&handle_response.data.unwrap()).unwrap() {
+            HandleAnswer::SetViewingKey { status } => assert_eq!(status, ResponseStatus::Success),
+            _ => panic!("Unexpected result from handle"),
+        };
+
+        // Key names follow the same limits scoped key names do
+        for name in vec![String::new(), "k".repeat(65)] {
+            let env = mock_env("alice", &[]);
+            let msg = HandleMsg::SetViewingKey { key: String::from("laptop key"), name: Some(name.to_string()), padding: None };
+            let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+            assert_eq!(error.code, "invalid_request");
+
+            let env = mock_env("alice", &[]);
+            let msg = HandleMsg::CreateViewingKey { entropy: String::from("laptop entropy"), name: Some(name), padding: None };
+            let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+            assert_eq!(error.code, "invalid_request");
+        }
+
+        // The key from init and both new keys all work at the same time
+        for key in vec![init_key.to_string(), phone_key.to_string(), String::from("desktop key")] {
+            let query_res = query(&deps, QueryMsg::GetWalletInfo { behalf: HumanAddr("alice".to_string()), key });
+            assert!(query_res.is_ok());
+        }
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetViewingKeys { behalf: HumanAddr("alice".to_string()), key: String::from("desktop key") },
+        );
+        let value: ViewingKeysResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.keys, vec![String::from("default"), String::from("phone"), String::from("desktop")]);
+        assert!(value.scoped_keys.is_empty());
+
+        // Revoking the phone key leaves the others working
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::RevokeViewingKey { name: String::from("phone") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::GetWalletInfo { behalf: HumanAddr("alice".to_string()), key: phone_key.to_string() });
+        assert!(query_res.is_err());
+        let query_res = query(&deps, QueryMsg::GetWalletInfo { behalf: HumanAddr("alice".to_string()), key: String::from("desktop key") });
+        assert!(query_res.is_ok());
+
+        // Setting a key under an existing name replaces it
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::SetViewingKey { key: String::from("new desktop key"), name: Some(String::from("desktop")), padding: None };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::GetWalletInfo { behalf: HumanAddr("alice".to_string()), key: String::from("desktop key") });
+        assert!(query_res.is_err());
+        let query_res = query(&deps, QueryMsg::GetWalletInfo { behalf: HumanAddr("alice".to_string()), key: String::from("new desktop key") });
+        assert!(query_res.is_ok());
+
+        // Revoking a key that doesn't exist fails
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::RevokeViewingKey { name: String::from("phone") };
+        assert!(handle(&mut deps, env, msg).is_err());
+    }
+
+    #[test]
+    fn error_codes_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+        let _vk = init_for_test(&mut deps, String::from("alice"));
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("I'm sad"),
+            path: String::from("anyone/test.txt"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Every failure carries a code alongside the message
+        let expect_code = |result: StdResult<HandleResponse>, code: &str| {
+            let error = parse_error(&result.unwrap_err()).unwrap();
+            assert_eq!(error.code, code);
+            assert!(!error.message.is_empty());
+        };
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Remove { path: String::from("anyone/nothing.txt") };
+        expect_code(handle(&mut deps, env, msg), "not_found");
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Remove { path: String::from("anyone/test.txt") };
+        expect_code(handle(&mut deps, env, msg), "unauthorized");
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateFolder { contents: String::from(""), path: String::from("anyone/movies/") };
+        expect_code(handle(&mut deps, env, msg), "already_exists");
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("dots"),
+            path: String::from("anyone/../alice/test.txt"),
+            content_type: None,
+        };
+        expect_code(handle(&mut deps, env, msg), "invalid_path");
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::SetRetention { path: Some(String::from("anyone/test.txt")), versions: Some(1_000_000) };
+        expect_code(handle(&mut deps, env, msg), "quota_exceeded");
+
+        // Queries use the same codes
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("anyone/test.txt"),
+                behalf: HumanAddr("anyone".to_string()),
+                key: String::from("wrong key"),
+            },
+        );
+        let error = parse_error(&query_res.unwrap_err()).unwrap();
+        assert_eq!(error.code, "unauthorized");
+    }
+
+    #[test]
+    fn malformed_input_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg { prng_seed: String::from("lets init bro") };
+        let env = mock_env("creator", &coins(20, "token"));
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        // InitAddress with fewer contents than paths is refused instead of reading past the end of contents_list
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::InitAddress {
+            contents_list: vec![String::from("root")],
+            path_list: vec![String::from("movies/"), String::from("memes/")],
+            entropy: String::from("entropy"),
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "invalid_request");
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::InitAddress { contents_list: vec![], path_list: vec![], entropy: String::from("entropy") };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        // Every path in path_list needs its parent folder to come first, and files can't be parents
+        for path_list in vec![
+            vec![String::from("notes.txt"), String::from("notes.txt/child")],
+            vec![String::from("a/b/")],
+            vec![String::from("a/b/"), String::from("a/")],
+        ] {
+            let env = mock_env("anyone", &[]);
+            let msg = HandleMsg::InitAddress {
+                contents_list: vec![String::from(""); path_list.len() + 1],
+                path_list,
+                entropy: String::from("entropy"),
+            };
+            let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+            assert_eq!(error.code, "invalid_path");
+        }
+
+        // A well formed InitAddress still works afterwards
+        let _vk = init_for_test(&mut deps, String::from("anyone"));
+
+        // Lists that don't line up are refused
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::CreateMulti {
+            contents_list: vec![String::from("a"), String::from("b")],
+            path_list: vec![String::from("anyone/a.txt")],
+        };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::MoveMulti {
+            old_path_list: vec![String::from("anyone/a.txt")],
+            new_path_list: vec![],
+            drop_acl: None,
+        };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        // Claiming a reward nobody set up is an error, not a trap
+        let env = mock_env("anyone", &[]);
+        let msg = HandleMsg::ClaimReward {
+            path: String::from("anyone/movies/"),
+            key: String::from("guess"),
+            address: String::from("anyone"),
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "not_found");
+
+        // Node queries work on an empty list
+        let query_res = query(&deps, QueryMsg::GetNodeIP { index: 3 });
+        assert!(query_res.is_ok());
+        let query_res = query(&deps, QueryMsg::GetNodeList { size: 10 });
+        assert!(query_res.is_ok());
+
+        // Queries that don't take a viewing key have no validation params
+        let msg = QueryMsg::GetNodeListSize {};
+        assert!(msg.get_validation_params().is_err());
+        assert!(msg.permission().is_err());
+    }
+
+    #[test]
+    fn namespace_layout_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg { prng_seed: String::from("lets init bro") };
+        let env = mock_env("creator", &coins(20, "token"));
+        let _res = init(&mut deps, env, msg).unwrap();
+        let vk2 = init_for_test(&mut deps, String::from("bob"));
+
+        // alice1 at generation 0 and alice at generation 10 used to share the namespace alice10
+        let alice = deps.api.canonical_address(&HumanAddr("alice".to_string())).unwrap();
+        let alice1 = deps.api.canonical_address(&HumanAddr("alice1".to_string())).unwrap();
+        assert_ne!(namespace_for(&alice1, 0), namespace_for(&alice, 10));
+        assert_ne!(namespace_for(&alice, 1), namespace_for(&alice, 10));
+
+        // alice's wallet and files the way the first release saved them: the wallet under her address string,
+        // records in the old shape under the namespace alice0, and no child index
+        #[derive(Serialize, Deserialize)]
+        struct BaselineWallet { init: bool, namespace: String, counter: i32, message_list_counter: i32 }
+        #[derive(Serialize, Deserialize)]
+        struct BaselineFile {
+            contents: String,
+            owner: String,
+            public: bool,
+            allow_read_list: OrderedSet<String>,
+            allow_write_list: OrderedSet<String>,
+        }
+
+        let wallet = BaselineWallet { init: true, namespace: String::from("alice0"), counter: 0, message_list_counter: 0 };
+        bucket(b"WALLET_INFO", &mut deps.storage).save(b"alice", &wallet).unwrap();
+
+        // The old release stored grantees as they were typed, so some don't spell an address the way it's stored now
+        let mut bob_can_read = OrderedSet::new();
+        bob_can_read.push(String::from("bob"));
+        bob_can_read.push(String::from("bob "));
+        bob_can_read.push(String::from("not an address at all"));
+        for (path, owner, contents, readers) in vec![
+            ("alice/", "alice", "root", OrderedSet::new()),
+            ("alice/movies/", "alice", "", OrderedSet::new()),
+            ("alice/movies/sunset.jpg", "alice ", "sunset", bob_can_read),
+            ("alice/notes.txt", "alice", "notes", OrderedSet::new()),
+            ("alice/todo.txt", "alice", "todo", OrderedSet::new()),
+            ("alice/old/photo.jpg", "alice", "photo", OrderedSet::new()),
+        ] {
+            let file = BaselineFile {
+                contents: String::from(contents),
+                owner: String::from(owner),
+                public: false,
+                allow_read_list: readers,
+                allow_write_list: OrderedSet::new(),
+            };
+            bucket(b"alice0", &mut deps.storage).save(path.as_bytes(), &file).unwrap();
+        }
+
+        // An unmigrated wallet still counts as initialized, so InitAddress can't start alice over and strand her files
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::InitAddress {
+            contents_list: vec![String::from("root")],
+            path_list: vec![],
+            entropy: String::from("entropy"),
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "already_exists");
+
+        // Only alice can migrate alice's files. bob can't name them, and migrating bob leaves alice where she was
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::MigrateStorage { paths: vec![String::from("alice/movies/")] };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "invalid_path");
+
+        let env = mock_env("bob", &[]);
+        let msg = HandleMsg::MigrateStorage { paths: vec![] };
+        let _res = handle(&mut deps, env, msg).unwrap();
+        let wallet = bucket_load_wallet(&deps.storage, &deps.api, "alice").unwrap().unwrap();
+        assert_eq!(wallet.namespace, "alice0");
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::SetViewingKey { key: String::from("alice key"), name: None, padding: None };
+        let _res = handle(&mut deps, env, msg).unwrap();
+        let vk = String::from("alice key");
+
+        // Before migrating, alice can still write over a file saved in the old shape. It's converted in place
+        // on the way, and what it held is kept as a prior version
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("todo v2"),
+            path: String::from("alice/todo.txt"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/todo.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.get_contents(), "todo v2");
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersionContents {
+                path: String::from("alice/todo.txt"),
+                version: 1,
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: VersionContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.version.contents, "todo");
+
+        // A path whose parent folder isn't migrated along with it, or that doesn't exist, fails the whole call
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::MigrateStorage { paths: vec![String::from("alice/old/photo.jpg")] };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "invalid_path");
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::MigrateStorage { paths: vec![String::from("alice/nothing.txt")] };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "not_found");
+
+        // alice migrates, listing a file before its folder and leaving notes.txt out for now
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::MigrateStorage {
+            paths: vec![String::from("alice/movies/sunset.jpg"), String::from("alice/movies/")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let current = namespace_for(&alice, 0);
+        let wallet = bucket_load_wallet(&deps.storage, &deps.api, "alice").unwrap().unwrap();
+        assert_eq!(wallet.namespace, current);
+        let legacy: Option<BaselineFile> = bucket_read(b"alice0", &deps.storage).may_load(b"alice/movies/sunset.jpg").unwrap();
+        assert!(legacy.is_none());
+
+        // The records came over in the current shape and the child index was rebuilt
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("alice/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("alice/movies/")]);
+        assert_eq!(value.files, vec![String::from("alice/todo.txt")]);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("alice/movies/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![String::from("alice/movies/sunset.jpg")]);
+
+        let file = bucket_load_readonly_file(&deps.storage, &String::from("alice/movies/"), &current).unwrap();
+        assert!(file.is_folder());
+
+        // Owners and grantees were normalized on the way, and grantees that aren't addresses were dropped
+        let file = bucket_load_readonly_file(&deps.storage, &String::from("alice/movies/sunset.jpg"), &current).unwrap();
+        assert_eq!(file.get_owner(), "alice");
+        assert_eq!(file.grantees(), vec![String::from("bob")]);
+
+        // bob's old read grant still works and now shows up as shared with him
+        let query_res = query(
+            &deps,
+            QueryMsg::GetContents {
+                path: String::from("alice/movies/sunset.jpg"),
+                behalf: HumanAddr("bob".to_string()),
+                key: vk2.to_string(),
+            },
+        );
+        let value: FileResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.file.get_contents(), "sunset");
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetSharedWithMe {
+                behalf: HumanAddr("bob".to_string()),
+                key: vk2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let value: SharedWithMeResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![SharedFile { path: String::from("alice/movies/sunset.jpg"), level: PermType::READ }]);
+
+        // Migrating again picks up the file left out, and skips what already moved
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::MigrateStorage {
+            paths: vec![String::from("alice/notes.txt"), String::from("alice/movies/")],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("alice/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.folders, vec![String::from("alice/movies/")]);
+        assert_eq!(value.files, vec![String::from("alice/todo.txt"), String::from("alice/notes.txt")]);
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetFolderContents {
+                path: String::from("alice/movies/"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: FolderContentsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.files, vec![String::from("alice/movies/sunset.jpg")]);
+
+        // Files made after the migration go into the new namespace and keep versions as usual
+        for contents in vec!["notes v2", "notes v3"] {
+            let env = mock_env("alice", &[]);
+            let msg = HandleMsg::Create {
+                contents: String::from(contents),
+                path: String::from("alice/notes.txt"),
+                content_type: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+
+        let query_res = query(
+            &deps,
+            QueryMsg::GetVersions {
+                path: String::from("alice/notes.txt"),
+                behalf: HumanAddr("alice".to_string()),
+                key: vk.to_string(),
+            },
+        );
+        let value: VersionsResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.versions.len(), 2);
+    }
+
+    #[test]
+    fn address_validation_test() {
+        let mut deps = mock_dependencies(20, &[]);
+        let _vk = init_for_test(&mut deps, String::from("alice"));
+        let _vk = init_for_test(&mut deps, String::from("bob"));
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::Create {
+            contents: String::from("sunset"),
+            path: String::from("alice/movies/sunset.jpg"),
+            content_type: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Stray whitespace and addresses the chain can't decode are refused before anything is stored
+        for bad in vec!["bob ", " bob", "", "an address that is far too long to be real"] {
+            let env = mock_env("alice", &[]);
+            let msg = HandleMsg::AllowRead {
+                path: String::from("alice/movies/sunset.jpg"),
+                message: String::from("for you"),
+                address_list: vec![String::from(bad)],
+                expires: None,
+            };
+            let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+            assert_eq!(error.code, "invalid_request");
+        }
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::CreateGroup { name: String::from("friends"), members: vec![String::from("bob ")] };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        // Groups are kept under their owner's address, so a reference naming the owner has to spell it validly too
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::CreateGroup { name: String::from("friends"), members: vec![String::from("bob")] };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("alice/movies/sunset.jpg"),
+            message: String::from("for my friends"),
+            address_list: vec![String::from("group:alice /friends")],
+            expires: None,
+        };
+        let error = parse_error(&handle(&mut deps, env, msg).unwrap_err()).unwrap();
+        assert_eq!(error.code, "invalid_request");
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("alice/movies/sunset.jpg"),
+            message: String::from("for my friends"),
+            address_list: vec![String::from("group:alice/friends")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::DeleteGroup { name: String::from("friends") };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::ProposeOwner {
+            path: String::from("alice/movies/sunset.jpg"),
+            message: String::from("take it"),
+            new_owner: String::from("bob "),
+        };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::SendMessage { to: HumanAddr("bob ".to_string()), contents: String::from("hi") };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::InitNode { ip: String::from("127.0.0.1"), address: String::from("") };
+        assert!(handle(&mut deps, env, msg).is_err());
+
+        let query_res = query(&deps, QueryMsg::YouUpBro { address: String::from("bob ") });
+        assert!(query_res.is_err());
+
+        // A valid address still works, and wallets are found by it after moving to canonical keys
+        let env = mock_env("alice", &[]);
+        let msg = HandleMsg::AllowRead {
+            path: String::from("alice/movies/sunset.jpg"),
+            message: String::from("for you"),
+            address_list: vec![String::from("bob")],
+            expires: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let query_res = query(&deps, QueryMsg::YouUpBro { address: String::from("bob") });
+        let value: WalletInfoResponse = from_binary(&query_res.unwrap()).unwrap();
+        assert_eq!(value.init, true);
+    }
+
 }
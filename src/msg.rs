@@ -1,8 +1,10 @@
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{HumanAddr, StdError, StdResult};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::{backend::File, viewing_key::ViewingKey, messaging::Message};
+use crate::{backend::{Expiration, File, FileMetadata, FileVersion, PermType, PermissionBlock}, viewing_key::ViewingKey, messaging::Message};
+use crate::error::ContractError;
+use crate::permit::{Permission, Permit};
 
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct InitMsg {
@@ -15,40 +17,126 @@ pub struct InitMsg {
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     InitAddress { contents_list: Vec<String>, path_list: Vec<String>, entropy: String },
-    Create {contents: String, path: String },
+    Create {contents: String, path: String, content_type: Option<String> },
     CreateMulti { contents_list: Vec<String>, path_list: Vec<String> },
+    CreateFolder {contents: String, path: String },
     Remove {path: String},
     RemoveMulti {path_list: Vec<String>},
-    Move {old_path: String, new_path: String},
-    MoveMulti {old_path_list: Vec<String>, new_path_list: Vec<String>},
-    CreateViewingKey {entropy: String, padding: Option<String>},
-    AllowRead {path: String, message: String, address_list: Vec<String>},
+    RemoveFolder {path: String, recursive: bool},
+    Move {old_path: String, new_path: String, drop_acl: Option<bool>},
+    MoveMulti {old_path_list: Vec<String>, new_path_list: Vec<String>, drop_acl: Option<bool>},
+    Copy {src: String, dst: String, recursive: bool},
+    CopyMulti {src_list: Vec<String>, dst_list: Vec<String>, recursive: bool},
+    RevertFile {path: String, version: u32},
+    SetRetention {path: Option<String>, versions: Option<u32>},
+    MakePublic {path: String},
+    MakePrivate {path: String},
+    SetInheritance {path: String, inherit: bool},
+    CreateViewingKey {entropy: String, name: Option<String>, padding: Option<String>},
+    SetViewingKey {key: String, name: Option<String>, padding: Option<String>},
+    RevokeViewingKey {name: String},
+    AllowRead {path: String, message: String, address_list: Vec<String>, expires: Option<Expiration>},
     DisallowRead {path: String, message: String, notify: bool, address_list: Vec<String>},
     ResetRead {path: String, message: String, notify: bool},
-    AllowWrite {path: String, message: String, address_list: Vec<String>},
+    AllowWrite {path: String, message: String, address_list: Vec<String>, expires: Option<Expiration>},
     DisallowWrite {path: String, message: String, notify: bool, address_list: Vec<String>},
     ResetWrite {path: String, message: String, notify: bool},
+    Grant {path: String, message: String, address_list: Vec<String>, level: PermType, expires: Option<Expiration>},
+    Revoke {path: String, message: String, notify: bool, address_list: Vec<String>},
+    Comment {path: String, contents: String},
     InitNode {ip: String, address: String},
     ClaimReward {path: String, key: String, address: String},
     ForgetMe { },
-    ChangeOwner {path: String, message: String, new_owner: String},
+    MigrateStorage { paths: Vec<String> },
+    ProposeOwner {path: String, message: String, new_owner: String},
+    AcceptOwnership {path: String},
+    DeclineOwnership {path: String},
+    CancelOwnership {path: String},
+    // Groups
+    CreateGroup { name: String, members: Vec<String> },
+    AddGroupMembers { name: String, members: Vec<String> },
+    RemoveGroupMembers { name: String, members: Vec<String> },
+    DeleteGroup { name: String },
+    // Permits
+    RevokePermit { permit_name: String },
+    // Scoped keys
+    CreateScopedKey {
+        name: String,
+        entropy: String,
+        path_prefix: Option<String>,
+        permissions: Vec<Permission>,
+        expires: Option<u64>,
+    },
+    RevokeScopedKey { name: String },
     // Messaging
     SendMessage { to: HumanAddr, contents: String },
     DeleteAllMessages {}
 }
 
+/**
+  Queries don't get an Env, so they can't see the current block. Grant expiries and scoped key expiries are judged
+  against the last block a handle ran in instead. On a contract nobody has sent a message to for a while, that block
+  is behind, and a grant or key that has expired since keeps working for queries until the next handle. Handles
+  always check against their own block.
+*/
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetContents { behalf: HumanAddr, path: String, key: String },
+    GetFolderContents { behalf: HumanAddr, path: String, key: String },
+    Stat { behalf: HumanAddr, path: String, key: String },
+    GetVersions { behalf: HumanAddr, path: String, key: String },
+    GetVersionContents { behalf: HumanAddr, path: String, key: String, version: u32 },
+    GetFolderTree { behalf: HumanAddr, path: String, key: String, max_depth: Option<u32>, max_entries: Option<u32> },
+    GetSharedWithMe { behalf: HumanAddr, key: String, start_after: Option<String>, limit: Option<u32> },
+    GetPermissions { behalf: HumanAddr, path: String, key: String },
+    CheckAccess { behalf: HumanAddr, path: String, key: String, address: String, level: PermType },
+    GetPublicContents { path: String },
     GetNodeIP {index: u64},
     GetNodeListSize {},
     GetNodeList{size: u64},
     GetNodeCoins{address: String},
     YouUpBro{address: String},
     GetWalletInfo { behalf: HumanAddr, key: String},
+    GetViewingKeys { behalf: HumanAddr, key: String },
     // Messaging
-    GetMessages { behalf: HumanAddr, key: String }
+    GetMessages { behalf: HumanAddr, key: String },
+    // The authenticated queries above, on behalf of whoever signed the permit
+    WithPermit { permit: Permit, query: QueryWithPermit },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    GetContents { path: String },
+    GetFolderContents { path: String },
+    Stat { path: String },
+    GetVersions { path: String },
+    GetVersionContents { path: String, version: u32 },
+    GetFolderTree { path: String, max_depth: Option<u32>, max_entries: Option<u32> },
+    GetSharedWithMe { start_after: Option<String>, limit: Option<u32> },
+    GetPermissions { path: String },
+    CheckAccess { path: String, address: String, level: PermType },
+    GetWalletInfo {},
+    GetViewingKeys {},
+    GetMessages {},
+}
+
+impl QueryWithPermit {
+    //what the permit has to allow for this query
+    pub fn permission(&self) -> Permission {
+        match self {
+            Self::GetContents { .. }
+            | Self::GetFolderContents { .. }
+            | Self::Stat { .. }
+            | Self::GetVersions { .. }
+            | Self::GetVersionContents { .. }
+            | Self::GetFolderTree { .. } => Permission::Files,
+            Self::GetSharedWithMe { .. } | Self::GetPermissions { .. } | Self::CheckAccess { .. } => Permission::Sharing,
+            Self::GetWalletInfo {} | Self::GetViewingKeys {} => Permission::WalletInfo,
+            Self::GetMessages {} => Permission::Messages,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
@@ -56,6 +144,8 @@ pub enum QueryMsg {
 pub enum HandleAnswer {
     DefaultAnswer { status:ResponseStatus},
     CreateViewingKey { key: ViewingKey },
+    CreateScopedKey { name: String, key: ViewingKey },
+    SetViewingKey { status: ResponseStatus },
 }
 
 // We define a custom struct for each query response
@@ -71,6 +161,28 @@ pub struct FileResponse {
     pub file: File,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PublicContentsResponse {
+    pub contents: String,
+    pub content_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatResponse {
+    pub metadata: FileMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionsResponse {
+    pub current: FileMetadata,
+    pub versions: Vec<FileMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionContentsResponse {
+    pub version: FileVersion,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FolderContentsResponse {
     pub parent: String,
@@ -84,17 +196,149 @@ pub struct BigTreeResponse {
     pub files: Vec<String>,
 }
 
-impl QueryMsg {
-    pub fn get_validation_params(&self) -> (Vec<&HumanAddr>, ViewingKey) {
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharedFile {
+    pub path: String,
+    pub level: PermType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InheritedPermissions {
+    pub path: String,
+    pub permissions: Vec<PermissionBlock>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermissionsResponse {
+    pub owner: String,
+    pub public: bool,
+    pub inherit: bool,
+    pub pending_owner: Option<String>,
+    pub permissions: Vec<PermissionBlock>,
+    // grants on the folders above, nearest first. Only there while inherit is true
+    pub inherited: Vec<InheritedPermissions>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScopedKeyInfo {
+    pub name: String,
+    pub path_prefix: Option<String>,
+    pub permissions: Vec<Permission>,
+    pub expires: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ViewingKeysResponse {
+    pub keys: Vec<String>,
+    pub scoped_keys: Vec<ScopedKeyInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckAccessResponse {
+    pub allowed: bool,
+}
+
+// Pass the last path of a page as start_after to get the next one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharedWithMeResponse {
+    pub files: Vec<SharedFile>,
+}
+
+impl HandleMsg {
+    //every path a handle message points at, so they can be validated before anything runs
+    pub fn get_paths(&self) -> Vec<&String> {
         match self {
+            Self::Create { path, .. } => vec![path],
+            Self::CreateMulti { path_list, .. } => path_list.iter().collect(),
+            Self::CreateFolder { path, .. } => vec![path],
+            Self::Remove { path } => vec![path],
+            Self::RemoveMulti { path_list } => path_list.iter().collect(),
+            Self::RemoveFolder { path, .. } => vec![path],
+            Self::Move { old_path, new_path, .. } => vec![old_path, new_path],
+            Self::MoveMulti { old_path_list, new_path_list, .. } => old_path_list.iter().chain(new_path_list.iter()).collect(),
+            Self::Copy { src, dst, .. } => vec![src, dst],
+            Self::CopyMulti { src_list, dst_list, .. } => src_list.iter().chain(dst_list.iter()).collect(),
+            Self::RevertFile { path, .. } => vec![path],
+            Self::SetRetention { path, .. } => path.iter().collect(),
+            Self::MakePublic { path } => vec![path],
+            Self::MakePrivate { path } => vec![path],
+            Self::SetInheritance { path, .. } => vec![path],
+            Self::AllowRead { path, .. } => vec![path],
+            Self::DisallowRead { path, .. } => vec![path],
+            Self::ResetRead { path, .. } => vec![path],
+            Self::AllowWrite { path, .. } => vec![path],
+            Self::DisallowWrite { path, .. } => vec![path],
+            Self::ResetWrite { path, .. } => vec![path],
+            Self::Grant { path, .. } => vec![path],
+            Self::Revoke { path, .. } => vec![path],
+            Self::Comment { path, .. } => vec![path],
+            Self::ProposeOwner { path, .. } => vec![path],
+            Self::AcceptOwnership { path } => vec![path],
+            Self::DeclineOwnership { path } => vec![path],
+            Self::CancelOwnership { path } => vec![path],
+            Self::CreateScopedKey { path_prefix, .. } => path_prefix.iter().collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl QueryMsg {
+    pub fn get_validation_params(&self) -> StdResult<(Vec<&HumanAddr>, ViewingKey)> {
+        let params = match self {
             Self::GetContents { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetFolderContents { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::Stat { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetVersions { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetVersionContents { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetFolderTree { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetSharedWithMe { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetPermissions { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::CheckAccess { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
             Self::GetWalletInfo { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetViewingKeys { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
             Self::GetMessages { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
-            _ => panic!("This query type does not require authentication"),
+            _ => return Err(not_authenticated()),
+        };
+        Ok(params)
+    }
+
+    //the query type a scoped key has to allow for this query
+    pub fn permission(&self) -> StdResult<Permission> {
+        let permission = match self {
+            Self::GetContents { .. }
+            | Self::GetFolderContents { .. }
+            | Self::Stat { .. }
+            | Self::GetVersions { .. }
+            | Self::GetVersionContents { .. }
+            | Self::GetFolderTree { .. } => Permission::Files,
+            Self::GetSharedWithMe { .. } | Self::GetPermissions { .. } | Self::CheckAccess { .. } => Permission::Sharing,
+            Self::GetWalletInfo { .. } | Self::GetViewingKeys { .. } => Permission::WalletInfo,
+            Self::GetMessages { .. } => Permission::Messages,
+            _ => return Err(not_authenticated()),
+        };
+        Ok(permission)
+    }
+
+    //the path an authenticated query looks at, if it takes one
+    pub fn path(&self) -> Option<&String> {
+        match self {
+            Self::GetContents { path, .. }
+            | Self::GetFolderContents { path, .. }
+            | Self::Stat { path, .. }
+            | Self::GetVersions { path, .. }
+            | Self::GetVersionContents { path, .. }
+            | Self::GetFolderTree { path, .. }
+            | Self::GetPermissions { path, .. }
+            | Self::CheckAccess { path, .. } => Some(path),
+            _ => None,
         }
     }
 }
 
+fn not_authenticated() -> StdError {
+    ContractError::InvalidRequest(String::from("This query type does not require authentication")).into()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ResponseStatus {
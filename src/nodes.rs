@@ -3,7 +3,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cosmwasm_storage::{ bucket, bucket_read};
-use cosmwasm_std::{to_binary, Api, Querier, Storage, StdResult, StdError, Extern, HandleResponse};
+use cosmwasm_std::{to_binary, Api, CanonicalAddr, Querier, Storage, StdResult, Extern, HandleResponse};
+
+use crate::address::{canonicalize, normalize};
+use crate::error::ContractError;
 
 
 static NODE_LOCATION: &[u8] = b"NODES";
@@ -18,6 +21,7 @@ pub fn pub_query_coins<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     address: String,
 ) -> StdResult<HandleResponse> {
+    let address = normalize(&deps.api, &address)?;
 
     Ok(HandleResponse {
         messages: vec![],
@@ -46,15 +50,18 @@ pub fn claim<S: Storage, A: Api, Q: Querier>(
     address: String
 )-> StdResult<HandleResponse> {
 
-    
+    let address = normalize(&deps.api, &address)?;
     let mut acl = String::from(&address);
     acl.push_str(&claim_path);
 
 
-    let resp:Result<String, StdError> = bucket_read(NODE_CLAIM_CODES, &deps.storage).load(acl.as_bytes());
-    let r = resp.unwrap();
+    let resp:Option<String> = bucket_read(NODE_CLAIM_CODES, &deps.storage).may_load(acl.as_bytes())?;
+    let r = match resp {
+        Some(r) => r,
+        None => return Err(ContractError::NotFound(format!("No claim code for {}", claim_path)).into()),
+    };
 
-    let count_resp:Result<u32, StdError> = bucket_read(COIN_COUNT, &deps.storage).load(address.as_bytes());
+    let count_resp:StdResult<u32> = bucket_read(COIN_COUNT, &deps.storage).load(address.as_bytes());
 
     let mut old_count:u32 = 0;
     match count_resp {
@@ -69,7 +76,7 @@ pub fn claim<S: Storage, A: Api, Q: Querier>(
 
     if claim_code.eq(&r)  {
 
-        let _bucket_response = bucket(COIN_COUNT, &mut deps.storage).save(address.as_bytes(), &old_count);
+        bucket(COIN_COUNT, &mut deps.storage).save(address.as_bytes(), &old_count)?;
 
         bucket::<S, String>(NODE_CLAIM_CODES, &mut deps.storage).remove(claim_path.as_bytes());
 
@@ -84,16 +91,12 @@ pub fn claim<S: Storage, A: Api, Q: Querier>(
 
 }
 
-pub fn write_claim<'a, S: Storage>(store: &'a mut S, claim_path: String, claim_code: String) {
+pub fn write_claim<'a, S: Storage>(store: &'a mut S, claim_path: String, claim_code: String) -> StdResult<()> {
 
     let c = &claim_path;
 
 
-    let bucket_response = bucket(NODE_CLAIM_CODES, store).save(c.as_bytes(), &claim_code);
-    match bucket_response {
-        Ok(bucket_response) => bucket_response,
-        Err(e) => panic!("Bucket Error: {}", e)
-    }
+    bucket(NODE_CLAIM_CODES, store).save(c.as_bytes(), &claim_code)
 }
 
 
@@ -102,83 +105,94 @@ pub fn write_claim<'a, S: Storage>(store: &'a mut S, claim_path: String, claim_c
 
 
 
+//Saved under a new field name, so records from before addresses were canonical can't be mistaken for current ones
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct NodeData {
+    score: u32,
+    #[serde(rename = "canonical_address")]
+    secret_address: CanonicalAddr,
+}
+
+impl NodeData {
+    pub fn secret_address(&self) -> &CanonicalAddr {
+        &self.secret_address
+    }
+}
+
+//The shape node records were saved in while the address was kept as a plain string
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct LegacyNodeData {
     score: u32,
     secret_address: String,
-} 
+}
 
-pub fn get_node<'a, S: Storage>(store: &'a S, index: u64) -> String {
-    let size = get_node_size(store);
+pub fn get_node<'a, S: Storage>(store: &'a S, index: u64) -> StdResult<String> {
+    let size = get_node_size(store)?;
 
     if index >= size {
-        return String::from("null");
+        return Ok(String::from("null"));
     }
 
     load_node_loc(store, index.to_string())
 }
 
-pub fn push_node<'a, S: Storage>(store: &'a mut S, ip: String, address: String) {
+pub fn push_node<'a, S: Storage>(store: &'a mut S, ip: String, address: CanonicalAddr) -> StdResult<()> {
 
-    let size = get_node_size(store);
+    let size = get_node_size(store)?;
 
 
-    save_node_loc(store, size.to_string(), ip.clone());
+    save_node_loc(store, size.to_string(), ip.clone())?;
 
     let node = NodeData {
         score: 500,
         secret_address: address
     };
 
-    save_node_data(store, ip, node);
+    save_node_data(store, ip, node)?;
 
     let size = size + 1;
 
-    set_node_size(store, size);
+    set_node_size(store, size)
 
 }
 
-pub fn set_node_size<'a, S: Storage>( store: &'a mut S, size: u64 ) {
-    let bucket_response = bucket(NODE_MAP_DATA, store).save("list_size".as_bytes(), &size);
-    match bucket_response {
-        Ok(bucket_response) => bucket_response,
-        Err(e) => panic!("Bucket Error: {}", e)
-    }
+pub fn set_node_size<'a, S: Storage>( store: &'a mut S, size: u64 ) -> StdResult<()> {
+    bucket(NODE_MAP_DATA, store).save("list_size".as_bytes(), &size)
 }
 
-pub fn get_node_size<'a, S: Storage>( store: &'a S) -> u64{
-    bucket_read(NODE_MAP_DATA, store).load("list_size".as_bytes()).unwrap()
+//the list size is written at init, so a missing one just means no nodes have been added
+pub fn get_node_size<'a, S: Storage>( store: &'a S) -> StdResult<u64> {
+    let size: Option<u64> = bucket_read(NODE_MAP_DATA, store).may_load("list_size".as_bytes())?;
+    Ok(size.unwrap_or(0))
 }
 
-pub fn save_node_loc<'a, S: Storage>( store: &'a mut S, loc: String, ipaddress: String ) {
-    let bucket_response = bucket(NODE_LOC_LOCATION, store).save(loc.as_bytes(), &ipaddress);
-    match bucket_response {
-        Ok(bucket_response) => bucket_response,
-        Err(e) => panic!("Bucket Error: {}", e)
-    }
+pub fn save_node_loc<'a, S: Storage>( store: &'a mut S, loc: String, ipaddress: String ) -> StdResult<()> {
+    bucket(NODE_LOC_LOCATION, store).save(loc.as_bytes(), &ipaddress)
 }
 
-pub fn load_node_loc<'a, S: Storage>( store: &'a S, loc: String) -> String{
-    bucket_read(NODE_LOC_LOCATION, store).load(loc.as_bytes()).unwrap()
-}
-
-
-pub fn save_node_data<'a, S: Storage>( store: &'a mut S, ipaddress: String, node_data: NodeData ) {
-
-
-
-    let bucket_response = bucket(NODE_LOCATION, store).save(ipaddress.as_bytes(), &node_data);
-
-
-    match bucket_response {
-        Ok(bucket_response) => bucket_response,
-        Err(e) => panic!("Bucket Error: {}", e)
+pub fn load_node_loc<'a, S: Storage>( store: &'a S, loc: String) -> StdResult<String> {
+    match bucket_read(NODE_LOC_LOCATION, store).may_load(loc.as_bytes())? {
+        Some(ipaddress) => Ok(ipaddress),
+        None => Err(ContractError::NotFound(format!("No node at index {}", loc)).into()),
     }
+}
 
 
+pub fn save_node_data<'a, S: Storage>( store: &'a mut S, ipaddress: String, node_data: NodeData ) -> StdResult<()> {
+    bucket(NODE_LOCATION, store).save(ipaddress.as_bytes(), &node_data)
 }
 
-pub fn load_node_data<'a, S: Storage>( store: &'a S, ipaddress: String) -> NodeData{
-    bucket_read(NODE_LOCATION, store).load(ipaddress.as_bytes()).unwrap()
+//Legacy records are converted as they're read, their address going through the same check a new node's does
+pub fn load_node_data<'a, S: Storage, A: Api>( store: &'a S, api: &A, ipaddress: String) -> StdResult<NodeData> {
+    if let Ok(Some(node_data)) = bucket_read::<S, NodeData>(NODE_LOCATION, store).may_load(ipaddress.as_bytes()) {
+        return Ok(node_data);
+    }
+    match bucket_read::<S, LegacyNodeData>(NODE_LOCATION, store).may_load(ipaddress.as_bytes())? {
+        Some(legacy) => Ok(NodeData {
+            score: legacy.score,
+            secret_address: canonicalize(api, &legacy.secret_address)?,
+        }),
+        None => Err(ContractError::NotFound(format!("No node at {}", ipaddress)).into()),
+    }
 }
 
@@ -51,6 +51,10 @@ impl<T: PartialEq + Sized> OrderedSet<T> {
         }
     }
 
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.data.retain(f);
+    }
+
     pub fn to_vec(&self) -> &Vec<T> {
         &self.data
     }
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use cosmwasm_std::{StdError, StdResult};
+
+use crate::error::ContractError;
+
+pub const MAX_PATH_LENGTH: usize = 512;
+pub const MAX_SEGMENT_LENGTH: usize = 128;
+pub const MAX_PATH_DEPTH: usize = 16;
+
+/**
+  A parsed storage path.
+
+  Grammar:
+    path    := segment ( '/' segment )* [ '/' ]
+    segment := one or more characters, other than '/' and control characters
+
+  The first segment is the root folder, named after the owner's address. A path that ends with '/'
+  is a folder, anything else is a file. A file can't live outside of a root folder, so a file path
+  needs at least two segments. Segments can't be "." or "..", and can't start or end with whitespace.
+  Paths are limited to MAX_PATH_LENGTH bytes, segments to MAX_SEGMENT_LENGTH bytes and MAX_PATH_DEPTH segments.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    segments: Vec<String>,
+    folder: bool,
+}
+
+impl Path {
+    pub fn parse(path: &str) -> StdResult<Path> {
+        if path.is_empty() {
+            return Err(invalid_path(path, "path is empty"));
+        }
+        if path.len() > MAX_PATH_LENGTH {
+            return Err(invalid_path(path, &format!("path is longer than {} bytes", MAX_PATH_LENGTH)));
+        }
+
+        let folder = path.ends_with('/');
+        let body = if folder { &path[..path.len() - 1] } else { path };
+
+        let mut segments: Vec<String> = Vec::new();
+        for segment in body.split('/') {
+            if segment.is_empty() {
+                return Err(invalid_path(path, "path contains an empty segment"));
+            }
+            if segment == "." || segment == ".." {
+                return Err(invalid_path(path, "'.' and '..' are not allowed"));
+            }
+            if segment.len() > MAX_SEGMENT_LENGTH {
+                return Err(invalid_path(path, &format!("a segment is longer than {} bytes", MAX_SEGMENT_LENGTH)));
+            }
+            if segment.trim() != segment {
+                return Err(invalid_path(path, "a segment starts or ends with whitespace"));
+            }
+            if segment.chars().any(|c| c.is_control()) {
+                return Err(invalid_path(path, "path contains control characters"));
+            }
+            segments.push(segment.to_string());
+        }
+
+        if segments.len() > MAX_PATH_DEPTH {
+            return Err(invalid_path(path, &format!("path is deeper than {} segments", MAX_PATH_DEPTH)));
+        }
+        if !folder && segments.len() < 2 {
+            return Err(invalid_path(path, "a file needs to be inside of a root folder"));
+        }
+
+        Ok(Path { segments, folder })
+    }
+
+    //the root folder's name, which is the owner's address
+    pub fn root(&self) -> &str {
+        &self.segments[0]
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.folder
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.len() == 1
+    }
+
+    pub fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    //the folder holding this path. Root folders have no parent
+    pub fn parent(&self) -> Option<Path> {
+        if self.is_root() {
+            return None;
+        }
+        Some(Path {
+            segments: self.segments[..self.segments.len() - 1].to_vec(),
+            folder: true,
+        })
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.segments.join("/"))?;
+        if self.folder {
+            write!(f, "/")?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_path(path: &str, reason: &str) -> StdError {
+    ContractError::InvalidPath(format!("Invalid path '{}': {}", path, reason)).into()
+}
@@ -0,0 +1,190 @@
+use std::convert::TryFrom;
+
+use bech32::{ToBase32, Variant};
+use k256::ecdsa::signature::Verifier;
+use k256::ecdsa::{Signature, VerifyingKey};
+use ripemd160::{Digest, Ripemd160};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use cosmwasm_std::{to_vec, Api, Binary, CanonicalAddr, Env, Extern, HandleResponse, HumanAddr, Querier, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+use secret_toolkit::crypto::sha_256;
+
+use crate::address::canonicalize;
+use crate::error::ContractError;
+use crate::state::{load, PermitConfig, PERMIT_CONFIG_KEY};
+
+static REVOKED_PERMITS_LOCATION: &[u8] = b"REVOKED_PERMITS";
+
+pub const BECH32_PREFIX: &str = "secret";
+pub const PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+
+/**
+  A query permit in the style of SNIP-24. The holder of a key signs the params offline, the same way a wallet
+  signs a transaction with a fixed fee, and anyone holding the permit can then run the queries it names on
+  behalf of the signer. The signer can revoke a permit by name with RevokePermit.
+*/
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitParams {
+    pub allowed_tokens: Vec<HumanAddr>,
+    pub permit_name: String,
+    pub chain_id: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitSignature {
+    pub pub_key: PubKey,
+    pub signature: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PubKey {
+    pub r#type: String,
+    pub value: Binary,
+}
+
+// The query types a permit can allow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    // contents, folder contents, stat, versions and trees
+    Files,
+    // shared with me, permissions and access checks
+    Sharing,
+    Messages,
+    WalletInfo,
+}
+
+// What actually gets signed. Fields have to stay in alphabetical order, the same as the amino JSON wallets produce
+#[derive(Serialize)]
+struct SignedPermit<'a> {
+    account_number: &'a str,
+    chain_id: &'a str,
+    fee: Fee<'a>,
+    memo: &'a str,
+    msgs: Vec<PermitMsg<'a>>,
+    sequence: &'a str,
+}
+
+#[derive(Serialize)]
+struct Fee<'a> {
+    amount: Vec<Coin<'a>>,
+    gas: &'a str,
+}
+
+#[derive(Serialize)]
+struct Coin<'a> {
+    amount: &'a str,
+    denom: &'a str,
+}
+
+#[derive(Serialize)]
+struct PermitMsg<'a> {
+    r#type: &'a str,
+    value: PermitContent<'a>,
+}
+
+#[derive(Serialize)]
+struct PermitContent<'a> {
+    allowed_tokens: &'a Vec<HumanAddr>,
+    permissions: &'a Vec<Permission>,
+    permit_name: &'a str,
+}
+
+impl PermitParams {
+    //the bytes a wallet signs for these params
+    pub fn sign_bytes(&self) -> StdResult<Vec<u8>> {
+        to_vec(&SignedPermit {
+            account_number: "0",
+            chain_id: &self.chain_id,
+            fee: Fee {
+                amount: vec![Coin { amount: "0", denom: "uscrt" }],
+                gas: "1",
+            },
+            memo: "",
+            msgs: vec![PermitMsg {
+                r#type: "query_permit",
+                value: PermitContent {
+                    allowed_tokens: &self.allowed_tokens,
+                    permissions: &self.permissions,
+                    permit_name: &self.permit_name,
+                },
+            }],
+            sequence: "0",
+        })
+    }
+}
+
+//The address that signed permit, provided it was signed for this contract and chain, allows permission and hasn't been revoked.
+//It comes back spelled the way stored records spell addresses
+pub fn validate_permit<S: Storage, A: Api>(store: &S, api: &A, permit: &Permit, permission: Permission) -> StdResult<HumanAddr> {
+    //contracts made before permits only have a config once they've handled a message since
+    let config: PermitConfig = load(store, PERMIT_CONFIG_KEY)
+        .map_err(|_| ContractError::Unauthorized(String::from("Permits aren't accepted until the contract has handled a message")))?;
+    let params = &permit.params;
+
+    if !params.allowed_tokens.contains(&config.contract_address) {
+        return Err(ContractError::Unauthorized(format!("Permit doesn't apply to contract {}", config.contract_address)).into());
+    }
+    if params.chain_id != config.chain_id {
+        return Err(ContractError::Unauthorized(format!("Permit was signed for chain {}", params.chain_id)).into());
+    }
+    if !params.permissions.contains(&permission) {
+        return Err(ContractError::Unauthorized(format!("Permit doesn't allow {:?} queries", permission)).into());
+    }
+
+    let pub_key = &permit.signature.pub_key;
+    if pub_key.r#type != PUB_KEY_TYPE {
+        return Err(ContractError::Unauthorized(format!("Unsupported public key type {}", pub_key.r#type)).into());
+    }
+    let verifying_key = VerifyingKey::from_sec1_bytes(pub_key.value.as_slice())
+        .map_err(|_| ContractError::Unauthorized(String::from("Invalid public key in permit")))?;
+    let signature = Signature::try_from(permit.signature.signature.as_slice())
+        .map_err(|_| ContractError::Unauthorized(String::from("Invalid signature in permit")))?;
+    verifying_key
+        .verify(&params.sign_bytes()?, &signature)
+        .map_err(|_| ContractError::Unauthorized(String::from("Permit signature doesn't match its params")))?;
+
+    let signer = pub_key_to_address(pub_key.value.as_slice())?;
+    let canonical_signer = canonicalize(api, signer.as_str())?;
+    if is_revoked(store, &canonical_signer, &params.permit_name)? {
+        return Err(ContractError::Unauthorized(format!("Permit {} has been revoked", params.permit_name)).into());
+    }
+
+    Ok(api.human_address(&canonical_signer)?)
+}
+
+//Cosmos addresses are the bech32 of ripemd160(sha256(compressed public key))
+pub fn pub_key_to_address(pub_key: &[u8]) -> StdResult<HumanAddr> {
+    let hash = Ripemd160::digest(&sha_256(pub_key));
+    let address = bech32::encode(BECH32_PREFIX, hash.to_base32(), Variant::Bech32)
+        .map_err(|e| ContractError::Internal(format!("Couldn't encode address: {}", e)))?;
+    Ok(HumanAddr(address))
+}
+
+//Revocations are kept under the canonical address, so the sender revoking and the key signing agree however either is spelled
+pub fn is_revoked<S: Storage>(store: &S, signer: &CanonicalAddr, permit_name: &str) -> StdResult<bool> {
+    let revoked: Option<bool> =
+        ReadonlyBucket::multilevel(&[REVOKED_PERMITS_LOCATION, signer.as_slice()], store).may_load(permit_name.as_bytes())?;
+    Ok(revoked.unwrap_or(false))
+}
+
+//Every permit the sender signed under permit_name stops working, including ones signed later with the same name
+pub fn try_revoke_permit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    permit_name: String,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    Bucket::multilevel(&[REVOKED_PERMITS_LOCATION, sender.as_slice()], &mut deps.storage)
+        .save(permit_name.as_bytes(), &true)?;
+
+    Ok(HandleResponse::default())
+}
@@ -0,0 +1,151 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use cosmwasm_std::{to_binary, Api, CanonicalAddr, Env, Extern, HandleResponse, Querier, StdResult, Storage};
+use cosmwasm_storage::{bucket, bucket_read};
+
+use crate::backend::BlockTime;
+use crate::error::ContractError;
+use crate::msg::HandleAnswer;
+use crate::path::Path;
+use crate::permit::Permission;
+use crate::state::{load, State, CONFIG_KEY};
+use crate::viewing_key::ViewingKey;
+
+static SCOPED_KEYS_LOCATION: &[u8] = b"SCOPED_KEYS";
+
+pub const MAX_SCOPED_KEYS: usize = 20;
+pub const MAX_KEY_NAME_LENGTH: usize = 64;
+
+/**
+  An extra viewing key for handing to an app. It only works for the query types in permissions, only for
+  paths under path_prefix if there is one, and only until block height expires if that is set.
+  A key with a path_prefix can't run queries that don't take a path, since those could show anything.
+*/
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct ScopedKey {
+    pub name: String,
+    pub hashed_key: Vec<u8>,
+    pub path_prefix: Option<String>,
+    pub permissions: Vec<Permission>,
+    pub expires: Option<u64>,
+}
+
+impl ScopedKey {
+    //Whether this key may run a query of type permission on path. Queries only know the last block a handle ran in,
+    //so a key can outlive its expiry on a quiet contract, see QueryMsg
+    pub fn check(&self, permission: &Permission, path: Option<&String>, now: &BlockTime) -> StdResult<()> {
+        if let Some(expires) = self.expires {
+            if now.height >= expires {
+                return Err(ContractError::Unauthorized(format!("Key {} has expired", self.name)).into());
+            }
+        }
+        if !self.permissions.contains(permission) {
+            return Err(ContractError::Unauthorized(format!("Key {} doesn't allow {:?} queries", self.name, permission)).into());
+        }
+        if let Some(prefix) = &self.path_prefix {
+            match path {
+                Some(path) if path.starts_with(prefix) => (),
+                _ => return Err(ContractError::Unauthorized(format!("Key {} only works under {}", self.name, prefix)).into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read_scoped_keys<S: Storage>(store: &S, owner: &CanonicalAddr) -> StdResult<Vec<ScopedKey>> {
+    let keys: Option<Vec<ScopedKey>> = bucket_read(SCOPED_KEYS_LOCATION, store).may_load(owner.as_slice())?;
+    Ok(keys.unwrap_or_default())
+}
+
+fn write_scoped_keys<S: Storage>(store: &mut S, owner: &CanonicalAddr, keys: &Vec<ScopedKey>) -> StdResult<()> {
+    let mut keys_bucket = bucket(SCOPED_KEYS_LOCATION, store);
+    if keys.is_empty() {
+        keys_bucket.remove(owner.as_slice());
+        return Ok(());
+    }
+    keys_bucket.save(owner.as_slice(), keys)
+}
+
+//The scoped key of owner's that key matches, if any. Every key gets compared so the time taken doesn't say which one matched
+pub fn find_scoped_key<S: Storage>(store: &S, owner: &CanonicalAddr, key: &ViewingKey) -> StdResult<Option<ScopedKey>> {
+    let mut found: Option<ScopedKey> = None;
+    for scoped in read_scoped_keys(store, owner)? {
+        if key.check_viewing_key(&scoped.hashed_key) && found.is_none() {
+            found = Some(scoped);
+        }
+    }
+    Ok(found)
+}
+
+//Names of scoped and named viewing keys alike
+pub fn validate_key_name(name: &str) -> StdResult<()> {
+    if name.is_empty() || name.len() > MAX_KEY_NAME_LENGTH {
+        return Err(ContractError::InvalidRequest(format!("Key names must be between 1 and {} bytes", MAX_KEY_NAME_LENGTH)).into());
+    }
+    Ok(())
+}
+
+pub fn try_create_scoped_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    entropy: String,
+    path_prefix: Option<String>,
+    permissions: Vec<Permission>,
+    expires: Option<u64>,
+) -> StdResult<HandleResponse> {
+    validate_key_name(&name)?;
+    if let Some(prefix) = &path_prefix {
+        if !Path::parse(prefix)?.is_folder() {
+            return Err(ContractError::InvalidRequest(String::from("A key can only be limited to a folder")).into());
+        }
+    }
+    if permissions.is_empty() {
+        return Err(ContractError::InvalidRequest(String::from("A key needs at least one query type")).into());
+    }
+
+    let owner = deps.api.canonical_address(&env.message.sender)?;
+    let mut keys = read_scoped_keys(&deps.storage, &owner)?;
+    if keys.iter().any(|k| k.name == name) {
+        return Err(ContractError::AlreadyExists(format!("Key {} already exists", name)).into());
+    }
+    if keys.len() >= MAX_SCOPED_KEYS {
+        return Err(ContractError::QuotaExceeded(format!("Wallets can't have more than {} scoped keys", MAX_SCOPED_KEYS)).into());
+    }
+
+    let config: State = load(&deps.storage, CONFIG_KEY)?;
+    let key = ViewingKey::new(&env, &config.prng_seed, (&entropy).as_ref());
+
+    keys.push(ScopedKey {
+        name: name.to_string(),
+        hashed_key: key.to_hashed().to_vec(),
+        path_prefix,
+        permissions,
+        expires,
+    });
+    write_scoped_keys(&mut deps.storage, &owner, &keys)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::CreateScopedKey { name, key })?),
+    })
+}
+
+pub fn try_revoke_scoped_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let owner = deps.api.canonical_address(&env.message.sender)?;
+    let mut keys = read_scoped_keys(&deps.storage, &owner)?;
+
+    let before = keys.len();
+    keys.retain(|k| k.name != name);
+    if keys.len() == before {
+        return Err(ContractError::NotFound(format!("Key {}", name)).into());
+    }
+    write_scoped_keys(&mut deps.storage, &owner, &keys)?;
+
+    Ok(HandleResponse::default())
+}
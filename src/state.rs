@@ -7,15 +7,22 @@ use serde::de::DeserializeOwned;
 
 // use std::collections::HashMap;
 
-use cosmwasm_std::{Storage, HumanAddr, StdResult, StdError, ReadonlyStorage, CanonicalAddr};
+use cosmwasm_std::{Env, Storage, HumanAddr, StdResult, StdError, ReadonlyStorage, CanonicalAddr};
 use cosmwasm_storage::{ReadonlyPrefixedStorage, PrefixedStorage};
 
+use crate::error::ContractError;
 use crate::viewing_key::ViewingKey;
 
 // use crate::backend::{Folder, File};
 
 pub static CONFIG_KEY: &[u8] = b"config";
+pub static PERMIT_CONFIG_KEY: &[u8] = b"permit_config";
 pub const PREFIX_VIEWING_KEY: &[u8] = b"viewingkey";
+pub const PREFIX_VIEWING_KEYS: &[u8] = b"viewingkeys";
+
+// The name CreateViewingKey and SetViewingKey use when none is given, and the name of keys from before wallets could hold several
+pub const DEFAULT_KEY_NAME: &str = "default";
+pub const MAX_VIEWING_KEYS: usize = 10;
 
 // static API_NAME: &str = "API";
 
@@ -25,6 +32,23 @@ pub struct State {
     pub prng_seed: Vec<u8>,
 }
 
+// What permits have to be signed for. Queries don't get an Env, so it's saved by init, or by the first handle on
+// contracts made before permits. It's kept apart from State, whose stored encoding deployed contracts depend on
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct PermitConfig {
+    pub contract_address: HumanAddr,
+    pub chain_id: String,
+}
+
+//Saves the permit config from env, unless one is saved already
+pub fn ensure_permit_config<S: Storage>(storage: &mut S, env: &Env) -> StdResult<()> {
+    if storage.get(PERMIT_CONFIG_KEY).is_some() {
+        return Ok(());
+    }
+    let config = PermitConfig { contract_address: env.contract.address.clone(), chain_id: env.block.chain_id.clone() };
+    save(storage, PERMIT_CONFIG_KEY, &config)
+}
+
 pub fn save<T: Serialize, S: Storage>(storage: &mut S, key: &[u8],value: &T) -> StdResult<()> {
     storage.set(key, &Bincode2::serialize(value)?);
     Ok(())
@@ -38,14 +62,57 @@ pub fn load<T: DeserializeOwned, S: ReadonlyStorage>(storage: &S, key: &[u8]) ->
     )
 }
 
-pub fn write_viewing_key<S: Storage>(store: &mut S, owner: &CanonicalAddr, key: &ViewingKey) {
-    let mut user_key_store = PrefixedStorage::new(PREFIX_VIEWING_KEY, store);
-    user_key_store.set(owner.as_slice(), &key.to_hashed());
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct StoredKey {
+    pub name: String,
+    pub hashed_key: Vec<u8>,
 }
 
-pub fn read_viewing_key<S: Storage>(store: &S, owner: &CanonicalAddr) -> Option<Vec<u8>> {
-    let user_key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEWING_KEY, store);
-    user_key_store.get(owner.as_slice())
+//Saves key under name, replacing the key that had that name before. Other keys keep working
+pub fn write_viewing_key<S: Storage>(store: &mut S, owner: &CanonicalAddr, name: &str, key: &ViewingKey) -> StdResult<()> {
+    let mut keys = read_viewing_keys(store, owner)?;
+    keys.retain(|k| k.name != name);
+    if keys.len() >= MAX_VIEWING_KEYS {
+        return Err(ContractError::QuotaExceeded(format!("Wallets can't have more than {} viewing keys", MAX_VIEWING_KEYS)).into());
+    }
+    keys.push(StoredKey { name: name.to_string(), hashed_key: key.to_hashed().to_vec() });
+    write_viewing_keys(store, owner, &keys)
+}
+
+//Returns whether there was a key called name
+pub fn remove_viewing_key<S: Storage>(store: &mut S, owner: &CanonicalAddr, name: &str) -> StdResult<bool> {
+    let mut keys = read_viewing_keys(store, owner)?;
+    let before = keys.len();
+    keys.retain(|k| k.name != name);
+    if keys.len() == before {
+        return Ok(false);
+    }
+    write_viewing_keys(store, owner, &keys)?;
+    Ok(true)
+}
+
+pub fn read_viewing_keys<S: ReadonlyStorage>(store: &S, owner: &CanonicalAddr) -> StdResult<Vec<StoredKey>> {
+    let user_key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEWING_KEYS, store);
+    if let Some(keys) = user_key_store.get(owner.as_slice()) {
+        return Bincode2::deserialize(&keys);
+    }
+
+    //wallets that haven't changed their keys since they could hold several still have the single key
+    let legacy_key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEWING_KEY, store);
+    Ok(match legacy_key_store.get(owner.as_slice()) {
+        Some(hashed_key) => vec![StoredKey { name: DEFAULT_KEY_NAME.to_string(), hashed_key }],
+        None => vec![],
+    })
+}
+
+fn write_viewing_keys<S: Storage>(store: &mut S, owner: &CanonicalAddr, keys: &Vec<StoredKey>) -> StdResult<()> {
+    let mut user_key_store = PrefixedStorage::new(PREFIX_VIEWING_KEYS, store);
+    user_key_store.set(owner.as_slice(), &Bincode2::serialize(keys)?);
+
+    //the single key, if there was one, is part of the list now
+    let mut legacy_key_store = PrefixedStorage::new(PREFIX_VIEWING_KEY, store);
+    legacy_key_store.remove(owner.as_slice());
+    Ok(())
 }
 
 